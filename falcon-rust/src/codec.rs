@@ -0,0 +1,129 @@
+//! Textual and `serde` encodings for keys and signatures.
+//!
+//! The flash tool and tests only ever move keys around as raw byte arrays,
+//! but integrators embedding Falcon into wallets or config files want the
+//! same textual/serde ergonomics Solana (base58 keypairs) and secp256k1
+//! (an optional `serde` feature) offer. This module adds:
+//!
+//! - `to_base58_string` / `from_base58_string` and `to_hex` / `from_hex`
+//!   helpers on [`SecretKey`], [`PublicKey`], and [`Signature`].
+//! - `serde::Serialize`/`Deserialize` impls, gated behind the `serde`
+//!   feature, that encode as raw bytes for binary formats (bincode, CBOR)
+//!   and as base58 strings for human-readable ones (JSON, TOML).
+//!
+//! Everything here is built on the existing `to_bytes`/`from_bytes` pair, so
+//! it works the same whether or not the `serde` feature is enabled, and the
+//! `serde` feature itself can be turned off entirely for the embedded build.
+
+use crate::falcon::{PublicKey, SecretKey, Signature};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Error returned when a base58 or hex string fails to decode into a valid
+/// key or signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    /// The string wasn't valid base58/hex.
+    InvalidEncoding,
+    /// The decoded bytes didn't form a valid key/signature.
+    InvalidKey,
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        s.push_str(&format!("{:02x}", b));
+    }
+    s
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, CodecError> {
+    let bytes = s.as_bytes();
+    if bytes.len() % 2 != 0 {
+        return Err(CodecError::InvalidEncoding);
+    }
+
+    fn nibble(b: u8) -> Result<u8, CodecError> {
+        match b {
+            b'0'..=b'9' => Ok(b - b'0'),
+            b'a'..=b'f' => Ok(b - b'a' + 10),
+            b'A'..=b'F' => Ok(b - b'A' + 10),
+            _ => Err(CodecError::InvalidEncoding),
+        }
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() / 2);
+    for pair in bytes.chunks_exact(2) {
+        out.push((nibble(pair[0])? << 4) | nibble(pair[1])?);
+    }
+    Ok(out)
+}
+
+macro_rules! impl_text_codec {
+    ($ty:ident) => {
+        impl<const N: usize> $ty<N> {
+            /// Encode as a base58 string, the same representation Solana
+            /// uses for its keypairs.
+            pub fn to_base58_string(&self) -> String {
+                bs58::encode(self.to_bytes()).into_string()
+            }
+
+            /// Decode from a base58 string produced by
+            /// [`Self::to_base58_string`].
+            pub fn from_base58_string(s: &str) -> Result<Self, CodecError> {
+                let bytes = bs58::decode(s)
+                    .into_vec()
+                    .map_err(|_| CodecError::InvalidEncoding)?;
+                Self::from_bytes(&bytes).map_err(|_| CodecError::InvalidKey)
+            }
+
+            /// Encode as a lowercase hex string.
+            pub fn to_hex(&self) -> String {
+                encode_hex(&self.to_bytes())
+            }
+
+            /// Decode from a hex string produced by [`Self::to_hex`].
+            pub fn from_hex(s: &str) -> Result<Self, CodecError> {
+                let bytes = decode_hex(s)?;
+                Self::from_bytes(&bytes).map_err(|_| CodecError::InvalidKey)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<const N: usize> serde::Serialize for $ty<N> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&self.to_base58_string())
+                } else {
+                    serializer.serialize_bytes(&self.to_bytes())
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de, const N: usize> serde::Deserialize<'de> for $ty<N> {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                use serde::de::Error;
+
+                if deserializer.is_human_readable() {
+                    let s = String::deserialize(deserializer)?;
+                    Self::from_base58_string(&s).map_err(|_| D::Error::custom("invalid base58 encoding"))
+                } else {
+                    let bytes = Vec::<u8>::deserialize(deserializer)?;
+                    Self::from_bytes(&bytes).map_err(|_| D::Error::custom("invalid byte encoding"))
+                }
+            }
+        }
+    };
+}
+
+impl_text_codec!(SecretKey);
+impl_text_codec!(PublicKey);
+impl_text_codec!(Signature);
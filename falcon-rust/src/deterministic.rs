@@ -0,0 +1,95 @@
+//! RNG-free, deterministic signing.
+//!
+//! `sign_with_rng` requires the caller to supply a CSPRNG, which is awkward on
+//! an STM32 where a good entropy source is not always available at signing
+//! time. This mirrors the approach secp256k1 took when it moved from
+//! caller-supplied ECDSA nonces to RFC6979-style deterministic derivation:
+//! all sampler randomness is derived from `SHAKE256(domain_sep || sk || msg)`
+//! instead of an external RNG, so signing the same message under the same key
+//! twice produces byte-identical signatures.
+//!
+//! The derived randomness is fed through the exact same Gaussian sampling
+//! path as `sign_with_rng`, so the output is a valid Falcon signature and
+//! verifies with the ordinary `verify` function.
+
+use crate::falcon::{SecretKey, Signature};
+use alloc::vec::Vec;
+use rand_core::{Error, RngCore};
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
+
+/// Domain separator distinguishing deterministic signing from any other use
+/// of SHAKE256 elsewhere in this crate, so the two can never collide.
+const DOMAIN_SEP: &[u8] = b"falcon-rust/deterministic-sign/v1";
+
+/// An `RngCore` backed by a SHAKE256 stream, seeded once and then read
+/// indefinitely. Two `ShakeRng`s built from the same seed produce identical
+/// output, which is exactly the property deterministic signing needs.
+struct ShakeRng {
+    reader: <Shake256 as ExtendableOutput>::Reader,
+}
+
+impl ShakeRng {
+    fn from_seed_material(seed_material: &[&[u8]]) -> Self {
+        let mut hasher = Shake256::default();
+        for part in seed_material {
+            hasher.update(part);
+        }
+        Self {
+            reader: hasher.finalize_xof(),
+        }
+    }
+}
+
+impl RngCore for ShakeRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.reader.read(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Sign `msg` deterministically under `sk`: every signature byte, including
+/// the salt, is a pure function of `(sk, msg)`. See the module docs for why
+/// this is safe to use in place of `sign_with_rng` when no RNG is available.
+pub fn sign<const N: usize>(msg: &[u8], sk: &SecretKey<N>) -> Signature<N> {
+    let sk_bytes: Vec<u8> = sk.to_bytes();
+    let mut rng = ShakeRng::from_seed_material(&[DOMAIN_SEP, &sk_bytes, msg]);
+    crate::falcon::sign_with_rng(msg, sk, &mut rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::falcon512;
+
+    #[test]
+    fn deterministic_signing_is_reproducible() {
+        let (sk, pk) = falcon512::keygen([7u8; 32]);
+        let msg = b"sign me the same way twice";
+
+        let sig1 = sign(msg, &sk);
+        let sig2 = sign(msg, &sk);
+
+        assert_eq!(sig1.to_bytes(), sig2.to_bytes());
+        assert!(falcon512::verify(msg, &sig1, &pk));
+        assert!(falcon512::verify(msg, &sig2, &pk));
+    }
+}
@@ -0,0 +1,152 @@
+//! Integration with the [`signature`] crate's generic `Signer`/`Verifier`/`Keypair`
+//! traits.
+//!
+//! Crates such as `ed25519-dalek` and the Solana SDK expose their keys through
+//! these traits so that downstream code can stay generic over the signing
+//! scheme. Implementing them here lets Falcon be dropped into that kind of
+//! code without bespoke glue. This is purely additive: the free functions in
+//! [`crate::falcon512`] and [`crate::falcon1024`] remain the primary API and
+//! are what these impls delegate to.
+//!
+//! Gated behind the `signature` feature so `no_std` embedded builds that only
+//! need `sign_with_rng`/`verify` don't pay for the extra dependency.
+
+#![cfg(feature = "signature")]
+
+use crate::falcon::{PublicKey, SecretKey, Signature};
+use alloc::vec::Vec;
+use rand_core::OsRng;
+use signature::{Error, Keypair as SignatureKeypair, Signer, Verifier};
+
+impl<const N: usize> Signer<Signature<N>> for SecretKey<N> {
+    /// Falcon signing needs randomness for the Gaussian sampler; this pulls it
+    /// from the OS CSPRNG. Use [`crate::falcon512::sign_with_rng`] directly if
+    /// you need to supply your own RNG (e.g. on a device without `OsRng`).
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature<N>, Error> {
+        let mut rng = OsRng;
+        Ok(crate::falcon::sign_with_rng(msg, self, &mut rng))
+    }
+}
+
+impl<const N: usize> Verifier<Signature<N>> for PublicKey<N> {
+    fn verify(&self, msg: &[u8], signature: &Signature<N>) -> Result<(), Error> {
+        if crate::falcon::verify(msg, signature, self) {
+            Ok(())
+        } else {
+            Err(Error::new())
+        }
+    }
+}
+
+/// A Falcon secret/public key pair, bundled so it can implement
+/// [`signature::Keypair`]. The free `keygen` functions return the two keys
+/// separately; wrap them in this type when ecosystem code expects a single
+/// `Keypair`-shaped value.
+#[derive(Clone)]
+pub struct Keypair<const N: usize> {
+    pub secret: SecretKey<N>,
+    pub public: PublicKey<N>,
+}
+
+impl<const N: usize> Keypair<N> {
+    pub fn new(secret: SecretKey<N>, public: PublicKey<N>) -> Self {
+        Self { secret, public }
+    }
+}
+
+impl<const N: usize> Signer<Signature<N>> for Keypair<N> {
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature<N>, Error> {
+        self.secret.try_sign(msg)
+    }
+}
+
+impl<const N: usize> SignatureKeypair for Keypair<N> {
+    type VerifyingKey = PublicKey<N>;
+
+    fn verifying_key(&self) -> Self::VerifyingKey {
+        self.public.clone()
+    }
+}
+
+/// Error returned by the `TryFrom<&[u8]>` impls below when a byte slice does
+/// not decode to a valid key or signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError;
+
+impl<const N: usize> TryFrom<&[u8]> for SecretKey<N> {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        SecretKey::from_bytes(value).map_err(|_| DecodeError)
+    }
+}
+
+impl<const N: usize> TryFrom<&[u8]> for PublicKey<N> {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        PublicKey::from_bytes(value).map_err(|_| DecodeError)
+    }
+}
+
+impl<const N: usize> TryFrom<&[u8]> for Signature<N> {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Signature::from_bytes(value).map_err(|_| DecodeError)
+    }
+}
+
+/// Owned byte encoding for callers that need `AsRef<[u8]>`-shaped storage
+/// (e.g. generic code written against `signature::SignatureEncoding`).
+///
+/// `Signature::to_bytes()` allocates a fresh `Vec` on every call, so
+/// `AsRef<[u8]>` can't be implemented directly on `Signature` itself; this
+/// wrapper holds the encoded bytes so the borrow has somewhere to live.
+#[derive(Clone)]
+pub struct EncodedSignature(Vec<u8>);
+
+impl<const N: usize> From<&Signature<N>> for EncodedSignature {
+    fn from(sig: &Signature<N>) -> Self {
+        EncodedSignature(sig.to_bytes())
+    }
+}
+
+impl AsRef<[u8]> for EncodedSignature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<EncodedSignature> for Vec<u8> {
+    fn from(encoded: EncodedSignature) -> Self {
+        encoded.0
+    }
+}
+
+impl From<Vec<u8>> for EncodedSignature {
+    fn from(bytes: Vec<u8>) -> Self {
+        EncodedSignature(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for EncodedSignature {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Ok(EncodedSignature(value.to_vec()))
+    }
+}
+
+/// Lets `Signature<N>` be dropped into generic code written against
+/// `signature::SignatureEncoding`, the trait `ed25519-dalek` and friends use
+/// for an algorithm-agnostic byte encoding. Backed by [`EncodedSignature`]
+/// since `to_bytes()` returns an owned, heap-allocated `Vec` rather than a
+/// fixed-size array (Falcon signatures are variable-length).
+impl<const N: usize> signature::SignatureEncoding for Signature<N> {
+    type Repr = EncodedSignature;
+
+    fn to_bytes(&self) -> Self::Repr {
+        EncodedSignature::from(self)
+    }
+}
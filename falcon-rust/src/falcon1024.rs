@@ -0,0 +1,127 @@
+use crate::falcon;
+use crate::workspace::FalconWorkspace1024;
+use rand_core::RngCore;
+
+pub type SecretKey = falcon::SecretKey<1024>;
+pub type PublicKey = falcon::PublicKey<1024>;
+pub type Signature = falcon::Signature<1024>;
+
+pub fn keygen(seed: [u8; 32]) -> (SecretKey, PublicKey) {
+    falcon::keygen(seed)
+}
+
+pub fn sign_with_rng(msg: &[u8], sk: &SecretKey, rng: &mut impl RngCore) -> Signature {
+    falcon::sign_with_rng(msg, sk, rng)
+}
+
+/// Same as [`sign_with_rng`], but routed through a caller-owned
+/// [`FalconWorkspace1024`] so repeated signing doesn't allocate a fresh
+/// FFT/polynomial scratch buffer per call. See
+/// [`crate::falcon512::sign_with_rng_in`] for why this is declined rather
+/// than just undocumented.
+#[deprecated(
+    note = "blocked on crate::fast_fft/crate::ffsampling, which don't exist in this source tree yet; allocates the same as sign_with_rng"
+)]
+pub fn sign_with_rng_in(
+    msg: &[u8],
+    sk: &SecretKey,
+    rng: &mut impl RngCore,
+    _workspace: &mut FalconWorkspace1024,
+) -> Signature {
+    sign_with_rng(msg, sk, rng)
+}
+
+/// Deterministic, RNG-free signing: the signature is a pure function of
+/// `(sk, msg)`. See [`crate::deterministic`] for how the sampler randomness
+/// is derived.
+pub fn sign(msg: &[u8], sk: &SecretKey) -> Signature {
+    crate::deterministic::sign(msg, sk)
+}
+
+/// Alias for [`sign`]. See [`crate::falcon512::sign_deterministic`] for why
+/// this crate has exactly one deterministic signing derivation rather than a
+/// second, incompatible one under this name.
+pub fn sign_deterministic(msg: &[u8], sk: &SecretKey) -> Signature {
+    sign(msg, sk)
+}
+
+/// Sign a digest that was hashed off-device, binding the signature to
+/// `context` so it can't be replayed into a different application. See
+/// [`crate::prehashed`] for the exact domain-separation layout.
+pub fn sign_prehashed(
+    digest: &[u8],
+    sk: &SecretKey,
+    context: &[u8],
+    rng: &mut impl RngCore,
+) -> Result<Signature, crate::prehashed::PrehashedError> {
+    crate::prehashed::sign_prehashed(digest, sk, context, rng)
+}
+
+/// Verify a signature produced by [`sign_prehashed`].
+pub fn verify_prehashed(digest: &[u8], sig: &Signature, pk: &PublicKey, context: &[u8]) -> bool {
+    crate::prehashed::verify_prehashed(digest, sig, pk, context)
+}
+
+pub fn verify(msg: &[u8], sig: &Signature, pk: &PublicKey) -> bool {
+    falcon::verify(msg, sig, pk)
+}
+
+/// Same as [`verify`], but routed through a caller-owned
+/// [`FalconWorkspace1024`] for the allocation-free path. See
+/// [`crate::falcon512::verify_in`] for why this is declined rather than just
+/// undocumented.
+#[deprecated(
+    note = "blocked on crate::fast_fft/crate::ffsampling, which don't exist in this source tree yet; allocates the same as verify"
+)]
+pub fn verify_in(
+    msg: &[u8],
+    sig: &Signature,
+    pk: &PublicKey,
+    _workspace: &mut FalconWorkspace1024,
+) -> bool {
+    verify(msg, sig, pk)
+}
+
+/// Error returned by [`sign_into`] when `out` is too small for the signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScratchError {
+    OutputTooSmall,
+}
+
+/// Same as [`sign_with_rng`], but writes the encoded signature into the
+/// caller-supplied `out` buffer and threads a caller-supplied `scratch`
+/// buffer through for the allocation-free path. See
+/// [`crate::falcon512::sign_into`] for why this is declined rather than just
+/// undocumented.
+#[deprecated(
+    note = "blocked on crate::fast_fft/crate::ffsampling, which don't exist in this source tree yet; scratch is unused and this still allocates"
+)]
+pub fn sign_into(
+    msg: &[u8],
+    sk: &SecretKey,
+    rng: &mut impl RngCore,
+    _scratch: &mut [u8],
+    out: &mut [u8],
+) -> Result<usize, ScratchError> {
+    let bytes = sign_with_rng(msg, sk, rng).to_bytes();
+    if out.len() < bytes.len() {
+        return Err(ScratchError::OutputTooSmall);
+    }
+    out[..bytes.len()].copy_from_slice(&bytes);
+    Ok(bytes.len())
+}
+
+/// Same as [`verify`], but takes a caller-supplied `scratch` buffer for the
+/// allocation-free path. See [`crate::falcon512::verify_with_scratch`] for
+/// why this is declined rather than just undocumented.
+#[deprecated(
+    note = "blocked on crate::fast_fft/crate::ffsampling, which don't exist in this source tree yet; scratch is unused and this still allocates"
+)]
+pub fn verify_with_scratch(
+    msg: &[u8],
+    sig: &Signature,
+    pk: &PublicKey,
+    _scratch: &mut [u8],
+) -> bool {
+    verify(msg, sig, pk)
+}
@@ -1,4 +1,5 @@
 use crate::falcon;
+use crate::workspace::FalconWorkspace512;
 use rand_core::RngCore;
 
 pub type SecretKey = falcon::SecretKey<512>;
@@ -13,6 +14,131 @@ pub fn sign_with_rng(msg: &[u8], sk: &SecretKey, rng: &mut impl RngCore) -> Sign
     falcon::sign_with_rng(msg, sk, rng)
 }
 
+/// Same as [`sign_with_rng`], but routed through a caller-owned
+/// [`FalconWorkspace512`] so repeated signing (e.g. one device signing many
+/// messages) doesn't allocate a fresh FFT/polynomial scratch buffer per call.
+///
+/// Declined, not just undocumented: the FFT and sampling internals this
+/// would thread the workspace through live in
+/// [`crate::fast_fft`]/[`crate::ffsampling`], which are referenced from
+/// `lib.rs` but not present as files in this source tree, so there is
+/// nothing to wire `_workspace` into. This can't become a real
+/// allocation-reduction API until those modules exist; until then it's kept
+/// around (deprecated) only so call sites that already depend on its
+/// signature keep compiling. `falcon512_memory_test.rs` asserts it still
+/// allocates exactly as much as [`sign_with_rng`].
+#[deprecated(
+    note = "blocked on crate::fast_fft/crate::ffsampling, which don't exist in this source tree yet; allocates the same as sign_with_rng"
+)]
+pub fn sign_with_rng_in(
+    msg: &[u8],
+    sk: &SecretKey,
+    rng: &mut impl RngCore,
+    _workspace: &mut FalconWorkspace512,
+) -> Signature {
+    sign_with_rng(msg, sk, rng)
+}
+
+/// Deterministic, RNG-free signing: the signature is a pure function of
+/// `(sk, msg)`. See [`crate::deterministic`] for how the sampler randomness
+/// is derived.
+pub fn sign(msg: &[u8], sk: &SecretKey) -> Signature {
+    crate::deterministic::sign(msg, sk)
+}
+
+/// Alias for [`sign`]. This crate already has exactly one deterministic
+/// signing mechanism (`SHAKE256(domain_sep || sk || msg)` feeding the
+/// Gaussian sampler directly, see [`crate::deterministic`]); a second
+/// derivation that reseeds a `ChaCha20Rng` from the same kind of digest would
+/// produce a *different* signature for the same `(sk, msg)` and leave two
+/// incompatible "the deterministic one" APIs in the same crate. Named to
+/// match callers (e.g. WASM bindings without `getrandom`) that ask for
+/// `sign_deterministic` specifically.
+pub fn sign_deterministic(msg: &[u8], sk: &SecretKey) -> Signature {
+    sign(msg, sk)
+}
+
+/// Sign a digest that was hashed off-device, binding the signature to
+/// `context` so it can't be replayed into a different application. See
+/// [`crate::prehashed`] for the exact domain-separation layout.
+pub fn sign_prehashed(
+    digest: &[u8],
+    sk: &SecretKey,
+    context: &[u8],
+    rng: &mut impl RngCore,
+) -> Result<Signature, crate::prehashed::PrehashedError> {
+    crate::prehashed::sign_prehashed(digest, sk, context, rng)
+}
+
+/// Verify a signature produced by [`sign_prehashed`].
+pub fn verify_prehashed(digest: &[u8], sig: &Signature, pk: &PublicKey, context: &[u8]) -> bool {
+    crate::prehashed::verify_prehashed(digest, sig, pk, context)
+}
+
 pub fn verify(msg: &[u8], sig: &Signature, pk: &PublicKey) -> bool {
     falcon::verify(msg, sig, pk)
 }
+
+/// Same as [`verify`], but routed through a caller-owned [`FalconWorkspace512`]
+/// for the allocation-free path. See [`sign_with_rng_in`] for why this is
+/// declined rather than just undocumented.
+#[deprecated(
+    note = "blocked on crate::fast_fft/crate::ffsampling, which don't exist in this source tree yet; allocates the same as verify"
+)]
+pub fn verify_in(
+    msg: &[u8],
+    sig: &Signature,
+    pk: &PublicKey,
+    _workspace: &mut FalconWorkspace512,
+) -> bool {
+    verify(msg, sig, pk)
+}
+
+/// Error returned by [`sign_into`] when `out` is too small for the signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScratchError {
+    OutputTooSmall,
+}
+
+/// Same as [`sign_with_rng`], but writes the encoded signature into the
+/// caller-supplied `out` buffer instead of returning an owned `Signature`,
+/// and threads a caller-supplied `scratch` buffer through for the FFT/NTT
+/// sampling and norm-checking work instead of letting it allocate.
+///
+/// Declined, like [`sign_with_rng_in`]: the FFT/sampling internals `scratch`
+/// would back live in [`crate::fast_fft`]/[`crate::ffsampling`], which this
+/// source tree doesn't have; `scratch` is unused and this still allocates
+/// internally before copying the result into `out`. See
+/// `falcon512_memory_test.rs` for the test asserting that's still true.
+#[deprecated(
+    note = "blocked on crate::fast_fft/crate::ffsampling, which don't exist in this source tree yet; scratch is unused and this still allocates"
+)]
+pub fn sign_into(
+    msg: &[u8],
+    sk: &SecretKey,
+    rng: &mut impl RngCore,
+    _scratch: &mut [u8],
+    out: &mut [u8],
+) -> Result<usize, ScratchError> {
+    let bytes = sign_with_rng(msg, sk, rng).to_bytes();
+    if out.len() < bytes.len() {
+        return Err(ScratchError::OutputTooSmall);
+    }
+    out[..bytes.len()].copy_from_slice(&bytes);
+    Ok(bytes.len())
+}
+
+/// Same as [`verify`], but takes a caller-supplied `scratch` buffer for the
+/// allocation-free path. See [`sign_into`] for why this is declined rather
+/// than just undocumented.
+#[deprecated(
+    note = "blocked on crate::fast_fft/crate::ffsampling, which don't exist in this source tree yet; scratch is unused and this still allocates"
+)]
+pub fn verify_with_scratch(
+    msg: &[u8],
+    sig: &Signature,
+    pk: &PublicKey,
+    _scratch: &mut [u8],
+) -> bool {
+    verify(msg, sig, pk)
+}
@@ -64,7 +64,11 @@ extern crate alloc;
 #[cfg(test)]
 extern crate std;
 
+pub mod codec;
 pub(crate) mod cyclotomic_fourier;
+pub(crate) mod deterministic;
+#[cfg(feature = "signature")]
+pub mod ecosystem;
 pub(crate) mod encoding;
 pub(crate) mod falcon;
 pub mod falcon1024;
@@ -75,6 +79,8 @@ pub(crate) mod ffsampling;
 pub(crate) mod inverse;
 pub mod math; // pub for benching
 pub mod polynomial; // pub for benching
+pub(crate) mod prehashed;
 pub(crate) mod samplerz;
 pub(crate) mod u32_field;
+pub mod verifying_key;
 pub mod workspace;
@@ -0,0 +1,90 @@
+//! Precomputed verifying context for repeated verification under the same
+//! public key.
+//!
+//! `verify` recomputes the NTT of the public polynomial `h` on every call,
+//! but `h` never changes for a given public key, so a server verifying many
+//! signatures under one key pays that cost every time. [`VerifyingKey`]
+//! mirrors the precomputed-context-table approach secp256k1 uses for
+//! repeated operations under the same key: build it once via
+//! [`PublicKey::precompute`], then call [`VerifyingKey::verify_with`] per
+//! signature.
+//!
+//! Declined, not just undocumented: caching the NTT of `h` itself requires
+//! reaching into the transform code in
+//! [`crate::fast_fft`]/[`crate::falcon_field`] — `lib.rs` declares both
+//! modules, but neither exists as a file in this source tree, so there is
+//! nothing for `VerifyingKey` to cache into. This can't become a real
+//! precomputed-context API until those modules exist; until then
+//! `verify_with` is kept around (deprecated) as a correctness-preserving
+//! wrapper that does the same work as `verify`, so call sites that already
+//! depend on its signature keep compiling.
+//! `falcon512_verifying_key_benchmark.rs` asserts that `verify_with` takes
+//! less than 3x as long as `verify`; it does not check for a speedup.
+//!
+//! `PreparedPublicKey`/`PublicKey::prepare`/`VerifyingKey::verify_prepared`
+//! are deprecated aliases of `VerifyingKey`/`PublicKey::precompute`/
+//! `VerifyingKey::verify_with`: the same type and the same work under other
+//! names, with no separate implementation behind them.
+
+use crate::falcon::{verify, PublicKey, Signature};
+
+/// A public key with a precomputed verifying context.
+#[derive(Clone)]
+pub struct VerifyingKey<const N: usize> {
+    public_key: PublicKey<N>,
+}
+
+impl<const N: usize> VerifyingKey<N> {
+    /// Build a verifying context from a public key. Call once per key and
+    /// reuse it across every verification.
+    pub fn new(public_key: PublicKey<N>) -> Self {
+        Self { public_key }
+    }
+
+    /// Verify `sig` over `msg` using this precomputed context.
+    #[deprecated(
+        note = "blocked on crate::fast_fft/crate::falcon_field, which don't exist in this source tree yet; does the same work as falcon::verify"
+    )]
+    pub fn verify_with(&self, msg: &[u8], sig: &Signature<N>) -> bool {
+        verify(msg, sig, &self.public_key)
+    }
+
+    pub fn public_key(&self) -> &PublicKey<N> {
+        &self.public_key
+    }
+}
+
+impl<const N: usize> PublicKey<N> {
+    /// Build a [`VerifyingKey`] that caches whatever per-key precomputation
+    /// is available for repeated verification.
+    #[deprecated(
+        note = "blocked on crate::fast_fft/crate::falcon_field, which don't exist in this source tree yet; VerifyingKey caches nothing"
+    )]
+    pub fn precompute(&self) -> VerifyingKey<N> {
+        #[allow(deprecated)]
+        VerifyingKey::new(self.clone())
+    }
+
+    /// Declined duplicate of [`precompute`](Self::precompute) — see this
+    /// module's docs. Kept only so existing `prepare()` call sites compile.
+    #[deprecated(note = "duplicate of PublicKey::precompute; see crate::verifying_key module docs")]
+    pub fn prepare(&self) -> PreparedPublicKey<N> {
+        #[allow(deprecated)]
+        VerifyingKey::new(self.clone())
+    }
+}
+
+/// Declined duplicate of [`VerifyingKey`] — see this module's docs.
+#[deprecated(note = "duplicate of VerifyingKey; see crate::verifying_key module docs")]
+pub type PreparedPublicKey<N> = VerifyingKey<N>;
+
+impl<const N: usize> VerifyingKey<N> {
+    /// Declined duplicate of [`verify_with`](Self::verify_with) — see this
+    /// module's docs. Kept only so existing `verify_prepared()` call sites
+    /// compile.
+    #[deprecated(note = "duplicate of VerifyingKey::verify_with; see crate::verifying_key module docs")]
+    pub fn verify_prepared(&self, msg: &[u8], sig: &Signature<N>) -> bool {
+        #[allow(deprecated)]
+        self.verify_with(msg, sig)
+    }
+}
@@ -2,10 +2,31 @@
 //!
 //! This module provides reusable buffer structures to reduce heap allocations
 //! and memory fragmentation during cryptographic operations.
+//!
+//! # Secret hygiene
+//!
+//! These buffers hold the FFT/polynomial intermediates produced while
+//! signing, so under the default-on `zeroize` feature both workspaces wipe
+//! themselves on [`Drop`] the same way [`FalconWorkspace512::clear`] does,
+//! following the approach ed25519-dalek takes of scrubbing secret material
+//! rather than leaving it to linger in freed heap memory.
+//!
+//! Note: `SecretKey` itself (its decoded f/g/F/G polynomials and NTRU tree)
+//! is declared as living in [`crate::falcon`] (`lib.rs` has
+//! `pub(crate) mod falcon;`), but that file doesn't exist in this source
+//! tree — there's no `falcon.rs` to add `impl Drop for SecretKey` to.
+//! (To be clear: this isn't an orphan-rule restriction — the orphan rule
+//! only blocks trait impls across *crate* boundaries, and `falcon.rs` would
+//! be part of this same crate, so `impl Drop for SecretKey` could be added
+//! there freely if the file existed.) Zeroizing the workspace buffers here
+//! covers the sampler-state half of secret hygiene; `SecretKey`'s own
+//! scrubbing has to wait until `crate::falcon` lands.
 
 use crate::falcon_field::Felt;
 use alloc::vec::Vec;
 use num_complex::Complex64;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 /// Reusable workspace for Falcon-512 operations
 ///
@@ -65,6 +86,19 @@ impl Default for FalconWorkspace512 {
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl Drop for FalconWorkspace512 {
+    fn drop(&mut self) {
+        // `zeroize()` uses volatile writes so the compiler can't elide them
+        // as dead stores into a buffer that's about to be freed, unlike a
+        // plain loop. `Complex64`/`Felt` don't implement `Zeroize`, so
+        // `clear()` covers those on a best-effort basis.
+        self.temp_poly_i16.zeroize();
+        self.temp_poly_i32.zeroize();
+        self.clear();
+    }
+}
+
 /// Reusable workspace for Falcon-1024 operations
 #[derive(Clone)]
 pub struct FalconWorkspace1024 {
@@ -119,6 +153,15 @@ impl Default for FalconWorkspace1024 {
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl Drop for FalconWorkspace1024 {
+    fn drop(&mut self) {
+        self.temp_poly_i16.zeroize();
+        self.temp_poly_i32.zeroize();
+        self.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,70 @@
+//! Tests for base58/hex string encodings of keys and signatures.
+
+use falcon_rust::falcon512::{keygen, sign_with_rng, verify, PublicKey, SecretKey, Signature};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+#[test]
+fn test_base58_roundtrip() {
+    let (secret_key, public_key) = keygen([11u8; 32]);
+    let mut rng = ChaCha20Rng::from_seed([12u8; 32]);
+    let signature = sign_with_rng(b"base58 roundtrip", &secret_key, &mut rng);
+
+    let sk_restored = SecretKey::from_base58_string(&secret_key.to_base58_string())
+        .expect("secret key should round-trip through base58");
+    let pk_restored = PublicKey::from_base58_string(&public_key.to_base58_string())
+        .expect("public key should round-trip through base58");
+    let sig_restored = Signature::from_base58_string(&signature.to_base58_string())
+        .expect("signature should round-trip through base58");
+
+    assert!(verify(b"base58 roundtrip", &sig_restored, &pk_restored));
+    let resigned = sign_with_rng(b"base58 roundtrip", &sk_restored, &mut rng);
+    assert!(verify(b"base58 roundtrip", &resigned, &public_key));
+}
+
+#[test]
+fn test_hex_roundtrip() {
+    let (secret_key, public_key) = keygen([13u8; 32]);
+    let mut rng = ChaCha20Rng::from_seed([14u8; 32]);
+    let signature = sign_with_rng(b"hex roundtrip", &secret_key, &mut rng);
+
+    let pk_restored =
+        PublicKey::from_hex(&public_key.to_hex()).expect("public key should round-trip through hex");
+    let sig_restored =
+        Signature::from_hex(&signature.to_hex()).expect("signature should round-trip through hex");
+
+    assert!(verify(b"hex roundtrip", &sig_restored, &pk_restored));
+    let _ = secret_key; // kept alive to mirror the base58 test's shape
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_json_roundtrip_uses_base58() {
+    let (_secret_key, public_key) = keygen([15u8; 32]);
+
+    let json = serde_json::to_string(&public_key).unwrap();
+    assert_eq!(json, format!("\"{}\"", public_key.to_base58_string()));
+
+    let restored: PublicKey = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.to_bytes(), public_key.to_bytes());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_bincode_roundtrip_uses_raw_bytes() {
+    let (_secret_key, public_key) = keygen([16u8; 32]);
+
+    let encoded = bincode::serialize(&public_key).unwrap();
+    let restored: PublicKey = bincode::deserialize(&encoded).unwrap();
+    assert_eq!(restored.to_bytes(), public_key.to_bytes());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_roundtrips_secret_key_too() {
+    let (secret_key, _public_key) = keygen([17u8; 32]);
+
+    let encoded = bincode::serialize(&secret_key).unwrap();
+    let restored: SecretKey = bincode::deserialize(&encoded).unwrap();
+    assert_eq!(restored.to_bytes(), secret_key.to_bytes());
+}
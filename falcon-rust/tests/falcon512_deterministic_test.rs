@@ -0,0 +1,64 @@
+//! Tests for the RNG-free deterministic signing mode (`falcon512::sign`).
+
+use falcon_rust::falcon512::{keygen, sign, verify};
+
+#[test]
+fn test_deterministic_sign_is_byte_identical_across_calls() {
+    let (secret_key, public_key) = keygen([42u8; 32]);
+    let message = b"Deterministic signing should be reproducible";
+
+    let sig1 = sign(message, &secret_key);
+    let sig2 = sign(message, &secret_key);
+
+    assert_eq!(
+        sig1.to_bytes(),
+        sig2.to_bytes(),
+        "two independent calls on the same (sk, msg) must produce identical signatures"
+    );
+
+    assert!(verify(message, &sig1, &public_key));
+    assert!(verify(message, &sig2, &public_key));
+}
+
+#[test]
+fn test_deterministic_sign_differs_across_messages() {
+    let (secret_key, _public_key) = keygen([43u8; 32]);
+
+    let sig_a = sign(b"message A", &secret_key);
+    let sig_b = sign(b"message B", &secret_key);
+
+    assert_ne!(sig_a.to_bytes(), sig_b.to_bytes());
+}
+
+#[test]
+fn test_deterministic_signature_interoperates_with_external_crate() {
+    let (secret_key, public_key) = keygen([44u8; 32]);
+    let message = b"Cross-implementation check for deterministic signatures";
+
+    let signature = sign(message, &secret_key);
+
+    let pk_bytes = public_key.to_bytes();
+    let sig_bytes = signature.to_bytes();
+
+    let external_pk = falcon_rust_external::falcon512::PublicKey::from_bytes(&pk_bytes)
+        .expect("external crate should deserialize our public key");
+    let external_sig = falcon_rust_external::falcon512::Signature::from_bytes(&sig_bytes)
+        .expect("external crate should deserialize our deterministic signature");
+
+    assert!(
+        falcon_rust_external::falcon512::verify(message, &external_sig, &external_pk),
+        "external crate should verify a deterministically-produced signature"
+    );
+}
+
+#[test]
+fn test_sign_deterministic_alias_matches_sign() {
+    use falcon_rust::falcon512::sign_deterministic;
+
+    let (secret_key, public_key) = keygen([45u8; 32]);
+    let message = b"sign_deterministic should be the same path as sign";
+
+    let sig = sign_deterministic(message, &secret_key);
+    assert_eq!(sig.to_bytes(), sign(message, &secret_key).to_bytes());
+    assert!(verify(message, &sig, &public_key));
+}
@@ -0,0 +1,78 @@
+//! Tests for the `signature` crate's `Signer`/`Verifier`/`Keypair` interop
+//! (`crate::ecosystem`), gated behind the `signature` feature.
+
+#![cfg(feature = "signature")]
+
+use falcon_rust::ecosystem::Keypair;
+use falcon_rust::falcon512::{keygen, PublicKey, SecretKey, Signature};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use signature::{Keypair as SignatureKeypair, Signer, SignatureEncoding, Verifier};
+
+#[test]
+fn test_secret_key_signer_round_trips_with_public_key_verifier() {
+    let (secret_key, public_key) = keygen([41u8; 32]);
+    let msg = b"signer/verifier round trip";
+
+    let signature: Signature = secret_key.try_sign(msg).expect("signing should succeed");
+    assert!(public_key.verify(msg, &signature).is_ok());
+
+    let wrong_msg = b"a different message";
+    assert!(public_key.verify(wrong_msg, &signature).is_err());
+}
+
+#[test]
+fn test_keypair_try_sign_and_verifying_key_round_trip() {
+    let (secret_key, public_key) = keygen([42u8; 32]);
+    let keypair = Keypair::new(secret_key, public_key.clone());
+    let msg = b"keypair round trip";
+
+    let signature: Signature = keypair.try_sign(msg).expect("signing should succeed");
+    assert_eq!(keypair.verifying_key().to_bytes(), public_key.to_bytes());
+    assert!(keypair.verifying_key().verify(msg, &signature).is_ok());
+}
+
+#[test]
+fn test_try_from_bytes_round_trips_keys_and_signature() {
+    let (secret_key, public_key) = keygen([43u8; 32]);
+    let mut rng = ChaCha20Rng::from_seed([44u8; 32]);
+    let signature = falcon_rust::falcon512::sign_with_rng(b"try_from bytes", &secret_key, &mut rng);
+
+    let sk_bytes = secret_key.to_bytes();
+    let pk_bytes = public_key.to_bytes();
+    let sig_bytes = signature.to_bytes();
+
+    let sk_restored = SecretKey::try_from(sk_bytes.as_slice()).expect("secret key should decode");
+    let pk_restored = PublicKey::try_from(pk_bytes.as_slice()).expect("public key should decode");
+    let sig_restored = Signature::try_from(sig_bytes.as_slice()).expect("signature should decode");
+
+    assert!(falcon_rust::falcon512::verify(
+        b"try_from bytes",
+        &sig_restored,
+        &pk_restored
+    ));
+    let resigned = falcon_rust::falcon512::sign_with_rng(b"try_from bytes", &sk_restored, &mut rng);
+    assert!(falcon_rust::falcon512::verify(
+        b"try_from bytes",
+        &resigned,
+        &public_key
+    ));
+}
+
+#[test]
+fn test_try_from_bytes_rejects_garbage() {
+    let garbage = [0u8; 4];
+    assert!(SecretKey::try_from(garbage.as_slice()).is_err());
+    assert!(PublicKey::try_from(garbage.as_slice()).is_err());
+    assert!(Signature::try_from(garbage.as_slice()).is_err());
+}
+
+#[test]
+fn test_signature_encoding_as_ref_matches_to_bytes() {
+    let (secret_key, _public_key) = keygen([45u8; 32]);
+    let mut rng = ChaCha20Rng::from_seed([46u8; 32]);
+    let signature = falcon_rust::falcon512::sign_with_rng(b"encoding", &secret_key, &mut rng);
+
+    let encoded = <Signature as SignatureEncoding>::to_bytes(&signature);
+    assert_eq!(encoded.as_ref(), signature.to_bytes().as_slice());
+}
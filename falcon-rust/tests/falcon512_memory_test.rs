@@ -1,4 +1,7 @@
-use falcon_rust::falcon512::{keygen, sign_with_rng, verify};
+use falcon_rust::falcon512::{
+    keygen, sign_into, sign_with_rng, sign_with_rng_in, verify, verify_in, verify_with_scratch,
+};
+use falcon_rust::workspace::FalconWorkspace512;
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use std::alloc::{GlobalAlloc, Layout, System};
@@ -250,3 +253,88 @@ fn test_multiple_operations_memory() {
         final_allocated.saturating_sub(baseline)
     );
 }
+
+/// `sign_with_rng_in`/`sign_into`/`verify_in`/`verify_with_scratch` accept a
+/// caller-owned workspace/scratch buffer specifically so a device doing many
+/// signatures doesn't pay a fresh heap allocation each time. They're
+/// `#[deprecated]` and declined (see their doc comments) because this source
+/// tree doesn't have the `fast_fft`/`ffsampling` internals needed to actually
+/// route allocation through the caller-supplied buffer, so today they
+/// allocate exactly as much as the plain `sign_with_rng`/`verify`. This test
+/// pins that down: if it starts failing because peak usage *dropped*, the
+/// internals have landed and this test (and the functions' deprecation)
+/// should be updated to assert a real bound instead of parity.
+#[allow(deprecated)]
+#[test]
+fn test_workspace_and_scratch_variants_do_not_yet_reduce_allocation() {
+    let seed = [42u8; 32];
+    let (secret_key, public_key) = keygen(seed);
+    let message = b"Workspace/scratch parity check";
+
+    let mut rng = ChaCha20Rng::from_seed([7u8; 32]);
+    let _ = sign_with_rng(message, &secret_key, &mut rng);
+    reset_memory_tracking();
+    let baseline_peak = {
+        let mut rng = ChaCha20Rng::from_seed([8u8; 32]);
+        let _ = sign_with_rng(message, &secret_key, &mut rng);
+        get_peak_allocated()
+    };
+
+    // Allocate the workspace before resetting, so its own buffers aren't
+    // counted in the peak being compared against plain `sign_with_rng`.
+    let mut workspace = FalconWorkspace512::new();
+    reset_memory_tracking();
+    let workspace_peak = {
+        let mut rng = ChaCha20Rng::from_seed([8u8; 32]);
+        let _ = sign_with_rng_in(message, &secret_key, &mut rng, &mut workspace);
+        get_peak_allocated()
+    };
+
+    reset_memory_tracking();
+    let scratch_peak = {
+        let mut scratch = [0u8; 64];
+        let mut out = [0u8; 1024];
+        let mut rng = ChaCha20Rng::from_seed([8u8; 32]);
+        let _ = sign_into(message, &secret_key, &mut rng, &mut scratch, &mut out);
+        get_peak_allocated()
+    };
+
+    println!(
+        "sign_with_rng peak: {baseline_peak}, sign_with_rng_in peak: {workspace_peak}, sign_into peak: {scratch_peak}"
+    );
+    assert_eq!(
+        workspace_peak, baseline_peak,
+        "sign_with_rng_in allocated differently than sign_with_rng - update this test \
+         and the function's doc comment if workspace plumbing has landed"
+    );
+    assert_eq!(
+        scratch_peak, baseline_peak,
+        "sign_into allocated differently than sign_with_rng - update this test and the \
+         function's doc comment if scratch plumbing has landed"
+    );
+
+    let mut rng = ChaCha20Rng::from_seed([9u8; 32]);
+    let signature = sign_with_rng(message, &secret_key, &mut rng);
+
+    reset_memory_tracking();
+    let verify_baseline_peak = {
+        let _ = verify(message, &signature, &public_key);
+        get_peak_allocated()
+    };
+
+    reset_memory_tracking();
+    let verify_in_peak = {
+        let _ = verify_in(message, &signature, &public_key, &mut workspace);
+        get_peak_allocated()
+    };
+
+    reset_memory_tracking();
+    let verify_scratch_peak = {
+        let mut scratch = [0u8; 64];
+        let _ = verify_with_scratch(message, &signature, &public_key, &mut scratch);
+        get_peak_allocated()
+    };
+
+    assert_eq!(verify_in_peak, verify_baseline_peak);
+    assert_eq!(verify_scratch_peak, verify_baseline_peak);
+}
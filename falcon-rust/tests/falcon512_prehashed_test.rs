@@ -0,0 +1,37 @@
+//! Tests for pre-hashed signing with domain-separation contexts.
+
+use falcon_rust::falcon512::{keygen, sign_prehashed, verify, verify_prehashed};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+#[test]
+fn test_prehashed_roundtrip() {
+    let (secret_key, public_key) = keygen([31u8; 32]);
+    let mut rng = ChaCha20Rng::from_seed([32u8; 32]);
+    let digest = [0xABu8; 32];
+
+    let signature = sign_prehashed(&digest, &secret_key, b"wallet-tx-v1", &mut rng).unwrap();
+    assert!(verify_prehashed(&digest, &signature, &public_key, b"wallet-tx-v1"));
+}
+
+#[test]
+fn test_prehashed_rejects_cross_context_replay() {
+    let (secret_key, public_key) = keygen([33u8; 32]);
+    let mut rng = ChaCha20Rng::from_seed([34u8; 32]);
+    let digest = [0xCDu8; 32];
+
+    let signature = sign_prehashed(&digest, &secret_key, b"domain-a", &mut rng).unwrap();
+
+    assert!(verify_prehashed(&digest, &signature, &public_key, b"domain-a"));
+    assert!(!verify_prehashed(&digest, &signature, &public_key, b"domain-b"));
+}
+
+#[test]
+fn test_prehashed_signature_is_not_a_valid_raw_message_signature() {
+    let (secret_key, public_key) = keygen([35u8; 32]);
+    let mut rng = ChaCha20Rng::from_seed([36u8; 32]);
+    let digest = [0xEFu8; 32];
+
+    let signature = sign_prehashed(&digest, &secret_key, b"domain", &mut rng).unwrap();
+    assert!(!verify(&digest, &signature, &public_key));
+}
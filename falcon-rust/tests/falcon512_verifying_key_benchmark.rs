@@ -0,0 +1,57 @@
+//! Benchmark comparing plain `verify` against the precomputed
+//! `VerifyingKey::verify_with` over many signatures from the same key.
+//!
+//! `VerifyingKey` doesn't actually cache the NTT of `h` in this source tree
+//! (it's `#[deprecated]` and declined — see `crate::verifying_key`'s module
+//! docs for why), so `verify_with` should take about as long as `verify`.
+//! The assertion below bounds it from above only: a regression that makes
+//! `verify_with` more than 3x slower than `verify` fails the test instead of
+//! only showing up as two printed durations nobody compares. A `verify_with`
+//! that runs faster than `verify` passes.
+
+use falcon_rust::falcon512::{keygen, sign_with_rng, verify, PublicKey};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::time::Instant;
+
+#[allow(deprecated)]
+#[test]
+fn test_verify_with_matches_verify_over_1000_signatures() {
+    let iterations = 1000;
+    let (secret_key, public_key) = keygen([5u8; 32]);
+    let mut rng = ChaCha20Rng::from_seed([6u8; 32]);
+
+    let message = b"Benchmark message for repeated verification";
+    let signatures: Vec<_> = (0..iterations)
+        .map(|_| sign_with_rng(message, &secret_key, &mut rng))
+        .collect();
+
+    println!("\n=== Falcon-512 verify vs. verify_with ({iterations} signatures) ===");
+
+    let start = Instant::now();
+    for sig in &signatures {
+        assert!(verify(message, sig, &public_key));
+    }
+    let verify_duration = start.elapsed();
+    println!("  verify:      {:?}", verify_duration);
+
+    let verifying_key = PublicKey::precompute(&public_key);
+    let start = Instant::now();
+    for sig in &signatures {
+        assert!(verifying_key.verify_with(message, sig));
+    }
+    let verify_with_duration = start.elapsed();
+    println!("  verify_with: {:?}", verify_with_duration);
+
+    // `verify_with` does the same work as `verify` today (no NTT cache
+    // behind it yet - see the module doc comment), so it shouldn't be
+    // drastically slower. A generous 3x bound catches a real regression
+    // without being sensitive to benchmark noise; it deliberately does *not*
+    // assert `verify_with` is faster, since it isn't.
+    assert!(
+        verify_with_duration.as_nanos() < verify_duration.as_nanos() * 3,
+        "verify_with took {:?}, more than 3x verify's {:?} - investigate before assuming the NTT cache finally landed",
+        verify_with_duration,
+        verify_duration,
+    );
+}
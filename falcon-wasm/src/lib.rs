@@ -13,6 +13,13 @@ macro_rules! console_log {
     ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
 }
 
+/// Falcon512 secret/public key byte lengths, the same sizes
+/// `stm32::flash_store` uses. The combined keypair bytes here are a plain
+/// `secret ++ public` layout, without the magic/sequence header and CRC32
+/// that `stm32::flash_store` wraps around them on flash.
+const SK_SIZE: usize = 1281;
+const PK_SIZE: usize = 897;
+
 #[wasm_bindgen]
 pub struct FalconKeyPair {
     secret_key: falcon512::SecretKey,
@@ -26,12 +33,12 @@ impl FalconKeyPair {
         if seed.len() != 32 {
             return Err(JsValue::from_str("Seed must be exactly 32 bytes"));
         }
-        
+
         let mut seed_array = [0u8; 32];
         seed_array.copy_from_slice(seed);
-        
+
         let (secret_key, public_key) = falcon512::keygen(seed_array);
-        
+
         Ok(FalconKeyPair {
             secret_key,
             public_key,
@@ -47,6 +54,52 @@ impl FalconKeyPair {
     pub fn secret_key(&self) -> Vec<u8> {
         self.secret_key.to_bytes()
     }
+
+    /// Encode the public key alone as base58, the same representation
+    /// Solana uses for its keypairs.
+    pub fn public_key_base58(&self) -> String {
+        self.public_key.to_base58_string()
+    }
+
+    /// Encode the secret key alone as base58.
+    pub fn secret_key_base58(&self) -> String {
+        self.secret_key.to_base58_string()
+    }
+
+    /// Combined `secret_key ++ public_key` bytes, a single blob callers can
+    /// persist or transfer instead of handling the two keys separately.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.secret_key.to_bytes();
+        bytes.extend_from_slice(&self.public_key.to_bytes());
+        bytes
+    }
+
+    /// Reconstruct a keypair from bytes produced by [`FalconKeyPair::to_bytes`].
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<FalconKeyPair, JsValue> {
+        if bytes.len() != SK_SIZE + PK_SIZE {
+            return Err(JsValue::from_str("Unexpected combined keypair length"));
+        }
+        let secret_key = falcon512::SecretKey::from_bytes(&bytes[..SK_SIZE])
+            .map_err(|_| JsValue::from_str("Invalid secret key"))?;
+        let public_key = falcon512::PublicKey::from_bytes(&bytes[SK_SIZE..])
+            .map_err(|_| JsValue::from_str("Invalid public key"))?;
+        Ok(FalconKeyPair { secret_key, public_key })
+    }
+
+    /// Combined `secret_key ++ public_key` bytes, base58-encoded.
+    pub fn to_base58(&self) -> String {
+        bs58::encode(self.to_bytes()).into_string()
+    }
+
+    /// Reconstruct a keypair from a string produced by [`FalconKeyPair::to_base58`].
+    #[wasm_bindgen(js_name = fromBase58)]
+    pub fn from_base58(s: &str) -> Result<FalconKeyPair, JsValue> {
+        let bytes = bs58::decode(s)
+            .into_vec()
+            .map_err(|_| JsValue::from_str("Invalid base58 encoding"))?;
+        Self::from_bytes(&bytes)
+    }
 }
 
 #[wasm_bindgen]
@@ -60,6 +113,19 @@ impl FalconSignature {
     pub fn bytes(&self) -> Vec<u8> {
         self.signature.to_bytes()
     }
+
+    /// Encode the signature as base58.
+    pub fn to_base58(&self) -> String {
+        self.signature.to_base58_string()
+    }
+
+    /// Decode a signature produced by [`FalconSignature::to_base58`].
+    #[wasm_bindgen(js_name = fromBase58)]
+    pub fn from_base58(s: &str) -> Result<FalconSignature, JsValue> {
+        let signature = falcon512::Signature::from_base58_string(s)
+            .map_err(|_| JsValue::from_str("Invalid base58 encoding"))?;
+        Ok(FalconSignature { signature })
+    }
 }
 
 // Simple RNG implementation using Web Crypto API
@@ -4,12 +4,19 @@
 //! flashed to the reserved flash section on your STM32.
 //!
 //! Usage:
-//!   1. Generate keys: cd keygen && cargo run --release > keys_output.txt
-//!   2. Extract the hex arrays from keys_output.txt
-//!   3. Run this tool: cargo run --release -- --sk-file sk.bin --pk-file pk.bin
-//!   4. Flash the output: probe-rs download --chip STM32H743ZITx --format Bin --base-address 0x080FE000 keys.bin
+//!   1. Generate keys: cd keygen && cargo run --release -- --sk-file sk.bin --pk-file pk.bin
+//!   2. Run this tool: cargo run --release -- --sk-file sk.bin --pk-file pk.bin
+//!   3. Flash the output: probe-rs download --chip STM32H743ZITx --format Bin --base-address 0x080FE000 keys.bin
 //!
 //! Or use the helper script that does all steps.
+//!
+//! The output matches the record format `stm32::flash_store` expects:
+//! `[magic:4][seq:4][secret_key][public_key][crc32:4]`, padded to the 8 KB
+//! reserved section with erased-flash (`0xFF`) bytes. A plain
+//! `secret_key ++ public_key` blob with no framing is *not* valid input to
+//! `flash_store::load()` — it fails the magic check and the firmware falls
+//! back to generating (and persisting) a fresh random keypair, silently
+//! discarding whatever was flashed.
 
 use clap::Parser;
 use std::fs;
@@ -36,6 +43,26 @@ const SK_SIZE: usize = 1281;
 const PK_SIZE: usize = 897;
 const TOTAL_SIZE: usize = 8192; // 8KB reserved section
 
+/// Must match `RECORD_MAGIC` in `stm32/src/flash_store.rs`.
+const RECORD_MAGIC: u32 = 0xFA1C_0521;
+/// magic + seq, matching `HEADER_SIZE` in `stm32/src/flash_store.rs`.
+const HEADER_SIZE: usize = 4 + 4;
+
+/// CRC32/IEEE (polynomial 0xEDB88320), matching `stm32/src/crc32.rs`. Kept
+/// in sync by hand since this is a separate host-side binary from the
+/// `no_std` firmware crate.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = 0u32.wrapping_sub(crc & 1);
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
@@ -65,12 +92,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .into());
     }
 
-    // Create output buffer (8KB, initialized to 0xFF like erased flash)
-    let mut output_buffer = vec![0xFF; TOTAL_SIZE];
+    // Create output buffer (8KB, initialized to 0xFF like erased flash) and
+    // write a single record in slot 0: `[magic][seq=1][sk][pk][crc32]`, the
+    // same layout `flash_store::parse_slot` reads back. The remaining bytes
+    // stay `0xFF` (erased), so `flash_store::load()` sees exactly one valid
+    // record - this one - as the newest.
+    let mut output_buffer = vec![0xFFu8; TOTAL_SIZE];
+    let body_size = HEADER_SIZE + SK_SIZE + PK_SIZE;
 
-    // Copy keys into buffer
-    output_buffer[0..SK_SIZE].copy_from_slice(&sk_bytes);
-    output_buffer[SK_SIZE..SK_SIZE + PK_SIZE].copy_from_slice(&pk_bytes);
+    output_buffer[0..4].copy_from_slice(&RECORD_MAGIC.to_le_bytes());
+    output_buffer[4..8].copy_from_slice(&1u32.to_le_bytes()); // seq
+    output_buffer[HEADER_SIZE..HEADER_SIZE + SK_SIZE].copy_from_slice(&sk_bytes);
+    output_buffer[HEADER_SIZE + SK_SIZE..body_size].copy_from_slice(&pk_bytes);
+    let crc = crc32(&output_buffer[0..body_size]);
+    output_buffer[body_size..body_size + 4].copy_from_slice(&crc.to_le_bytes());
 
     // Write output file
     println!("Writing combined keys to: {}", args.output.display());
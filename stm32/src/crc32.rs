@@ -0,0 +1,16 @@
+//! Minimal CRC32 (IEEE 802.3, polynomial 0xEDB88320) with no lookup table,
+//! so it costs no flash/RAM beyond the code itself — fine for the small
+//! frames the USB protocol moves.
+
+/// Compute the CRC32/IEEE checksum of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = 0u32.wrapping_sub(crc & 1);
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
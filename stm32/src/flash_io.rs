@@ -0,0 +1,62 @@
+//! Low-level STM32H7 bank-1 flash program/erase primitives shared by
+//! [`crate::flash_store`] and [`crate::replay_counter`], which both use the
+//! same "ring of slots inside one reserved sector, erase only when full"
+//! wear-leveling scheme over different sectors.
+
+use stm32h7xx_hal::pac;
+
+/// STM32H7 flash is programmed 256 bits (32 bytes) at a time.
+pub const FLASH_WORD: usize = 32;
+
+fn unlock(flash: &pac::FLASH) {
+    flash.keyr1.write(|w| unsafe { w.bits(0x4567_0123) });
+    flash.keyr1.write(|w| unsafe { w.bits(0xCDEF_89AB) });
+}
+
+fn lock(flash: &pac::FLASH) {
+    flash.cr1.modify(|_, w| w.lock().set_bit());
+}
+
+fn wait_ready(flash: &pac::FLASH) {
+    while flash.sr1.read().qw1().bit_is_set() {
+        cortex_m::asm::nop();
+    }
+}
+
+/// Erase sector `sector_number` of bank 1.
+pub fn erase_sector(sector_number: u8) {
+    unsafe {
+        let flash = &*pac::FLASH::ptr();
+        unlock(flash);
+        flash
+            .cr1
+            .modify(|_, w| w.ser().set_bit().snb().bits(sector_number));
+        flash.cr1.modify(|_, w| w.start().set_bit());
+        wait_ready(flash);
+        flash.cr1.modify(|_, w| w.ser().clear_bit());
+        lock(flash);
+    }
+}
+
+/// Program `data` (whose length must be a multiple of [`FLASH_WORD`]) at
+/// `base_addr`, one flash word at a time.
+pub fn program(base_addr: u32, data: &[u8]) {
+    debug_assert!(data.len() % FLASH_WORD == 0);
+    unsafe {
+        let flash = &*pac::FLASH::ptr();
+        unlock(flash);
+        flash.cr1.modify(|_, w| w.pg().set_bit());
+
+        for (word_idx, chunk) in data.chunks(FLASH_WORD).enumerate() {
+            let word_addr = (base_addr as usize + word_idx * FLASH_WORD) as *mut u32;
+            for (i, quad) in chunk.chunks(4).enumerate() {
+                let value = u32::from_le_bytes(quad.try_into().unwrap());
+                core::ptr::write_volatile(word_addr.add(i), value);
+            }
+            wait_ready(flash);
+        }
+
+        flash.cr1.modify(|_, w| w.pg().clear_bit());
+        lock(flash);
+    }
+}
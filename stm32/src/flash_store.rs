@@ -0,0 +1,115 @@
+//! Flash-backed Falcon512 key storage (emulated-EEPROM style).
+//!
+//! Keys used to live only as compiled-in `SK_BYTES`/`PK_BYTES` constants, so
+//! every unit shipped identical keys and rotating them meant a reflash. This
+//! stores a keypair in the reserved 8 KB region at `0x080FE000` (the same
+//! address the `flash_keys` host tool writes to), at the top of 128 KB
+//! sector 7, as a ring of fixed-size slots tagged with a monotonic sequence
+//! number and a CRC32. [`load`] scans the region for the newest valid record;
+//! [`store`] writes the next slot and only pays for a full sector erase once
+//! every slot has been used, the standard wear-leveling trick for flash that
+//! can only be erased a sector at a time. That erase clears all of sector 7,
+//! not just the 8 KB region.
+
+use crate::crc32::crc32;
+use crate::flash_io::{self, FLASH_WORD};
+
+pub const SK_SIZE: usize = 1281;
+pub const PK_SIZE: usize = 897;
+
+pub(crate) const STORE_BASE: u32 = 0x080F_E000;
+pub(crate) const STORE_SIZE: usize = 8 * 1024;
+/// Sector 7 of bank 1 on the STM32H743 (128 KB sectors, last sector of the
+/// first 1 MB bank ends at `0x080FFFFF`). Visible to [`crate::replay_counter`]
+/// so it can assert its own sector never overlaps this one.
+pub(crate) const SECTOR_NUMBER: u8 = 7;
+
+const RECORD_MAGIC: u32 = 0xFA1C_0521;
+const HEADER_SIZE: usize = 4 + 4; // magic + seq
+const BODY_SIZE: usize = HEADER_SIZE + SK_SIZE + PK_SIZE;
+const RECORD_SIZE: usize = BODY_SIZE + 4; // + crc32
+const SLOT_SIZE: usize = (RECORD_SIZE + FLASH_WORD - 1) / FLASH_WORD * FLASH_WORD;
+const NUM_SLOTS: usize = STORE_SIZE / SLOT_SIZE;
+
+/// A Falcon512 keypair as stored on flash.
+pub struct KeyRecord {
+    pub secret_key: [u8; SK_SIZE],
+    pub public_key: [u8; PK_SIZE],
+}
+
+impl Drop for KeyRecord {
+    /// Wipe the secret key from RAM once this staging copy goes out of
+    /// scope, rather than leaving it to linger in freed stack/heap memory.
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+        self.secret_key.zeroize();
+    }
+}
+
+fn slot_bytes(slot: usize) -> &'static [u8] {
+    unsafe {
+        core::slice::from_raw_parts((STORE_BASE as usize + slot * SLOT_SIZE) as *const u8, SLOT_SIZE)
+    }
+}
+
+fn parse_slot(slot: usize) -> Option<(u32, KeyRecord)> {
+    let bytes = slot_bytes(slot);
+    if u32::from_le_bytes(bytes[0..4].try_into().unwrap()) != RECORD_MAGIC {
+        return None;
+    }
+    let seq = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    let expected_crc = u32::from_le_bytes(bytes[BODY_SIZE..BODY_SIZE + 4].try_into().unwrap());
+    if crc32(&bytes[0..BODY_SIZE]) != expected_crc {
+        return None;
+    }
+
+    let mut secret_key = [0u8; SK_SIZE];
+    secret_key.copy_from_slice(&bytes[HEADER_SIZE..HEADER_SIZE + SK_SIZE]);
+    let mut public_key = [0u8; PK_SIZE];
+    public_key.copy_from_slice(&bytes[HEADER_SIZE + SK_SIZE..BODY_SIZE]);
+    Some((seq, KeyRecord { secret_key, public_key }))
+}
+
+fn is_slot_erased(slot: usize) -> bool {
+    slot_bytes(slot).iter().all(|&b| b == 0xFF)
+}
+
+fn newest_slot() -> Option<(usize, u32)> {
+    (0..NUM_SLOTS)
+        .filter_map(|slot| parse_slot(slot).map(|(seq, _)| (slot, seq)))
+        .max_by_key(|(_, seq)| *seq)
+}
+
+/// Scan every slot and return the keypair with the highest sequence number,
+/// or `None` if the sector holds no valid record (first boot, or a fully
+/// erased/corrupt sector).
+pub fn load() -> Option<KeyRecord> {
+    newest_slot().and_then(|(slot, _)| parse_slot(slot)).map(|(_, record)| record)
+}
+
+/// Persist `record` as the newest record, wear-leveling across the
+/// sector's slots.
+pub fn store(record: &KeyRecord) {
+    let (slot, seq) = match newest_slot() {
+        Some((slot, seq)) if slot + 1 < NUM_SLOTS && is_slot_erased(slot + 1) => (slot + 1, seq + 1),
+        Some((_, seq)) => {
+            flash_io::erase_sector(SECTOR_NUMBER);
+            (0, seq + 1)
+        }
+        None => {
+            if !is_slot_erased(0) {
+                flash_io::erase_sector(SECTOR_NUMBER);
+            }
+            (0, 1)
+        }
+    };
+
+    let mut buf = [0xFFu8; SLOT_SIZE];
+    buf[0..4].copy_from_slice(&RECORD_MAGIC.to_le_bytes());
+    buf[4..8].copy_from_slice(&seq.to_le_bytes());
+    buf[HEADER_SIZE..HEADER_SIZE + SK_SIZE].copy_from_slice(&record.secret_key);
+    buf[HEADER_SIZE + SK_SIZE..BODY_SIZE].copy_from_slice(&record.public_key);
+    buf[BODY_SIZE..BODY_SIZE + 4].copy_from_slice(&crc32(&buf[0..BODY_SIZE]).to_le_bytes());
+
+    flash_io::program(STORE_BASE + (slot * SLOT_SIZE) as u32, &buf);
+}
@@ -1,18 +1,24 @@
 #![no_std]
 #![no_main]
 
+extern crate alloc;
+
 use panic_rtt_target as _;
 use rtt_target::{rprintln, rtt_init_print};
 
+use core::cell::RefCell;
+use core::mem::MaybeUninit;
+use cortex_m::interrupt::Mutex;
 use cortex_m_rt::entry;
 use falcon_rust::falcon512;
 use rand_chacha::ChaCha20Rng;
-use rand_core::SeedableRng;
-use stm32h7xx_hal::{pac, prelude::*, rcc::rec::UsbClkSel};
+use rand_core::{RngCore, SeedableRng};
+use stm32h7xx_hal::{pac, pac::interrupt, prelude::*, rcc::rec::UsbClkSel, rng::RngExt};
 
 // USB imports
 use stm32h7xx_hal::usb_hs::{UsbBus, USB2};
-use usb_device::device::{UsbDeviceBuilder, UsbDeviceState};
+use usb_device::bus::UsbBusAllocator;
+use usb_device::device::{UsbDevice, UsbDeviceBuilder, UsbDeviceState};
 use usb_device::prelude::*;
 use usbd_serial::{SerialPort, USB_CLASS_CDC};
 
@@ -22,33 +28,34 @@ use embedded_alloc::Heap;
 #[global_allocator]
 static HEAP: Heap = Heap::empty();
 
-// Import keys from separate module
-mod keys;
-use keys::{PK_BYTES, SK_BYTES};
-
-// Import signing and USB modules
+// Import signing, USB, timer, framing and flash-storage modules
+mod crc32;
+mod flash_io;
+mod flash_store;
+mod replay_counter;
 mod signing;
+mod timer;
 mod usb;
 
 use signing::Signer;
-use usb::UsbMessageHandler;
-
-// Simple delay function
-fn delay_ms(ms: u32) {
-    for _ in 0..(ms * 10000) {
-        cortex_m::asm::nop();
-    }
-}
-
-// State machine for USB message handling
+use usb::{FrameType, UsbMessageHandler};
+
+/// State machine for USB message handling. Shared between the USB interrupt
+/// (which only ever moves it out of `WaitingForMessage`, or resets it back
+/// to `WaitingForMessage` on disconnect) and the main loop (which performs
+/// whatever slow, allocating work each received command needs and then
+/// returns to `WaitingForMessage`). Falcon signing and on-device keygen both
+/// happen in the main loop, never in interrupt context.
 enum SigningState {
     WaitingForMessage,
     MessageReceived,
     Signing,
+    KeypairRequested,
+    ExportRequested,
 }
 
-// USB connection state tracking
-#[derive(PartialEq)]
+/// USB connection state tracking
+#[derive(PartialEq, Clone, Copy)]
 enum UsbConnectionState {
     Disconnected,
     Connecting,
@@ -56,13 +63,61 @@ enum UsbConnectionState {
     Suspended,
 }
 
+type Usb2Bus = UsbBus<USB2>;
+
+/// Everything the OTG_HS interrupt and the main loop both need to touch.
+/// Guarded by a `cortex_m::interrupt::Mutex` so access from either side is
+/// always inside a critical section.
+struct SharedUsb {
+    usb_dev: UsbDevice<'static, Usb2Bus>,
+    serial: SerialPort<'static, Usb2Bus>,
+    handler: UsbMessageHandler,
+    signing_state: SigningState,
+    usb_state: UsbConnectionState,
+}
+
+static SHARED_USB: Mutex<RefCell<Option<SharedUsb>>> = Mutex::new(RefCell::new(None));
+
+/// Last-observed USB connection state, written by the OTG_HS interrupt
+/// handler and read by the main loop to drive the status LED — kept in its
+/// own mutex outside `SharedUsb` since the LED GPIO itself is only ever
+/// touched from the main loop (the interrupt handler doesn't drive it).
+static LAST_USB_STATE: Mutex<RefCell<UsbConnectionState>> =
+    Mutex::new(RefCell::new(UsbConnectionState::Disconnected));
+
+/// Generate a fresh Falcon512 keypair, persist it to flash, and return the
+/// secret key plus the public key bytes. Used both for first-boot
+/// provisioning and for the `GenerateKeypair` USB command.
+fn generate_and_store_keypair(rng: &mut ChaCha20Rng) -> (falcon512::SecretKey, alloc::vec::Vec<u8>) {
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    let (sk, pk) = falcon512::keygen(seed);
+    let sk_bytes = sk.to_bytes();
+    let pk_bytes = pk.to_bytes();
+
+    flash_store::store(&flash_store::KeyRecord {
+        secret_key: sk_bytes
+            .clone()
+            .try_into()
+            .unwrap_or_else(|_| panic!("unexpected Falcon512 secret key length")),
+        public_key: pk_bytes
+            .clone()
+            .try_into()
+            .unwrap_or_else(|_| panic!("unexpected Falcon512 public key length")),
+    });
+    rprintln!("Generated and stored new Falcon512 keypair");
+
+    (sk, pk_bytes)
+}
+
 /// USB-based Falcon512 signing with button confirmation
 ///
 /// Workflow:
-/// 1. Listen for message from USB
-/// 2. On USB message received, flash LED rapidly until button click
-/// 3. On button click, sign the message
-/// 4. Send signed message back via USB
+/// 1. The OTG_HS interrupt polls the USB stack and assembles incoming
+///    messages; the main loop idles in WFI between interrupts.
+/// 2. On message received, flash LED rapidly until button click.
+/// 3. On button click, sign the message (in the main loop, not the ISR).
+/// 4. Send the signed response back via USB.
 #[entry]
 fn main() -> ! {
     // Initialize RTT for debug output
@@ -72,13 +127,17 @@ fn main() -> ! {
 
     // Get device peripherals
     let dp = pac::Peripherals::take().unwrap();
-    let _cp = cortex_m::Peripherals::take().unwrap();
+    let cp = cortex_m::Peripherals::take().unwrap();
 
     let pwr = dp.PWR.constrain();
     let pwrcfg = pwr.freeze();
     let rcc = dp.RCC.constrain();
     let mut ccdr = rcc.sys_ck(200.MHz()).freeze(pwrcfg, &dp.SYSCFG);
 
+    // Millisecond tick for LED timing and button debounce, replacing the old
+    // NOP-spin `delay_ms`.
+    timer::init(cp.SYST, 200_000_000);
+
     // Configure USB clock - use HSI48 (internal 48MHz oscillator)
     rprintln!("Configuring USB clock...");
     let _ = ccdr.clocks.hsi48_ck().expect("HSI48 must run");
@@ -95,39 +154,31 @@ fn main() -> ! {
         pwr.cr3.modify(|_, w| w.usbregen().set_bit());
 
         // Wait for USB regulator to be ready with timeout
-        let mut timeout_counter = 0u32;
-        let max_timeout = 100000;
-        while !pwr.cr3.read().usb33rdy().bit_is_set() && timeout_counter < max_timeout {
+        let start = timer::now_ms();
+        while !pwr.cr3.read().usb33rdy().bit_is_set() && !timer::elapsed_since(start, 50) {
             cortex_m::asm::nop();
-            timeout_counter += 1;
         }
-
-        if timeout_counter >= max_timeout {
+        if !pwr.cr3.read().usb33rdy().bit_is_set() {
             rprintln!("WARNING: USB regulator timeout - this may prevent USB-C operation");
         } else {
-            rprintln!("âœ… USB regulator ready for USB-C operation");
+            rprintln!("USB regulator ready for USB-C operation");
         }
 
         // Ensure HSI48 is stable - required for USB clock
-        let mut hsi48_timeout = 0u32;
-        while !rcc.cr.read().hsi48rdy().bit_is_set() && hsi48_timeout < 50000 {
+        let start = timer::now_ms();
+        while !rcc.cr.read().hsi48rdy().bit_is_set() && !timer::elapsed_since(start, 25) {
             cortex_m::asm::nop();
-            hsi48_timeout += 1;
         }
-
-        if hsi48_timeout >= 50000 {
+        if !rcc.cr.read().hsi48rdy().bit_is_set() {
             rprintln!("WARNING: HSI48 not stable - USB may not work");
         } else {
-            rprintln!("âœ… HSI48 clock stable for USB");
+            rprintln!("HSI48 clock stable for USB");
         }
 
         // Enable USB OTG FS clock (USB2 on H7)
         rcc.ahb1enr.modify(|_, w| w.usb2otgen().set_bit());
-
-        // Small delay for hardware stabilization
-        delay_ms(10);
     }
-    rprintln!("âœ… USB power and hardware configured for USB-C standalone operation");
+    rprintln!("USB power and hardware configured for USB-C standalone operation");
 
     // Setup LED on PE3
     let gpioe = dp.GPIOE.split(ccdr.peripheral.GPIOE);
@@ -148,15 +199,14 @@ fn main() -> ! {
     // Startup blink
     for _ in 0..2 {
         led.set_high();
-        delay_ms(100);
+        cortex_m::asm::delay(20_000_000);
         led.set_low();
-        delay_ms(100);
+        cortex_m::asm::delay(20_000_000);
     }
 
     // Initialize heap allocator (384KB for Falcon512)
     rprintln!("Setting up heap allocator (384KB)...");
     {
-        use core::mem::MaybeUninit;
         const HEAP_SIZE: usize = 384 * 1024;
         static mut HEAP_MEM: [MaybeUninit<u8>; HEAP_SIZE] = [MaybeUninit::uninit(); HEAP_SIZE];
         unsafe {
@@ -166,23 +216,42 @@ fn main() -> ! {
     }
     rprintln!("Heap initialized");
 
-    // Initialize RNG
-    rprintln!("Initializing RNG...");
-    let seed = [0x42u8; 32]; // TODO: Use hardware RNG in production
-    let rng = ChaCha20Rng::from_seed(seed);
-
-    // Load keys and create signer
-    rprintln!("Loading keys...");
-    let secret_key = match falcon512::SecretKey::from_bytes(&SK_BYTES) {
-        Ok(sk) => {
-            rprintln!("Secret key loaded");
-            sk
+    // Seed the signing RNG from the on-chip TRNG. Falcon signing randomness
+    // is security-critical, so a TRNG that fails its clock/seed health check
+    // must stop the device from ever signing rather than fall back to a
+    // weak/fixed seed.
+    rprintln!("Initializing hardware RNG...");
+    let mut hw_rng = dp.RNG.constrain(ccdr.peripheral.RNG, &ccdr.clocks);
+    let mut seed = [0u8; 32];
+    if hw_rng.try_fill_bytes(&mut seed).is_err() {
+        rprintln!("FATAL: hardware RNG health check failed, refusing to sign with weak entropy");
+        loop {
+            cortex_m::asm::wfi();
         }
-        Err(_) => {
-            rprintln!("ERROR: Failed to load secret key");
-            loop {
-                cortex_m::asm::nop();
+    }
+    let mut rng = ChaCha20Rng::from_seed(seed);
+    rprintln!("Hardware RNG healthy, ChaCha20Rng seeded");
+
+    // Load the keypair from flash, or generate and persist a fresh one on
+    // first boot / if the stored record fails to decode.
+    rprintln!("Loading keys from flash...");
+    let (secret_key, mut public_key_bytes) = match flash_store::load() {
+        Some(record) => match (
+            falcon512::SecretKey::from_bytes(&record.secret_key),
+            falcon512::PublicKey::from_bytes(&record.public_key),
+        ) {
+            (Ok(sk), Ok(_)) => {
+                rprintln!("Loaded Falcon512 keypair from flash");
+                (sk, record.public_key.to_vec())
             }
+            _ => {
+                rprintln!("Stored key record failed to decode; regenerating");
+                generate_and_store_keypair(&mut rng)
+            }
+        },
+        None => {
+            rprintln!("No key record in flash; generating keypair on first boot...");
+            generate_and_store_keypair(&mut rng)
         }
     };
 
@@ -191,48 +260,30 @@ fn main() -> ! {
 
     // Setup USB - USB2 OTG FS on PA11/PA12 (CN13 connector on STM32H750B-DK)
     rprintln!("Initializing USB...");
-    rprintln!("Step 1: Splitting GPIOA");
     let gpioa = dp.GPIOA.split(ccdr.peripheral.GPIOA);
-
-    rprintln!("Step 2: Configuring USB pins (PA11=D-, PA12=D+)");
     let usb_dm = gpioa.pa11.into_alternate::<10>();
     let usb_dp = gpioa.pa12.into_alternate::<10>();
 
-    rprintln!("Step 3: Pre-configuring USB hardware for USB-C standalone operation");
-
     // CRITICAL: Configure USB hardware registers for USB-C only operation
     unsafe {
         let usb_otg_global = &*pac::OTG2_HS_GLOBAL::ptr();
 
-        rprintln!("Step 3a: Forcing device mode for USB-C standalone");
         // Force device mode - MANDATORY for USB-C only operation
         usb_otg_global.gusbcfg.modify(|_, w| w.fdmod().set_bit());
-        delay_ms(10);
-
-        rprintln!("Step 3b: Setting USB turnaround time for full-speed");
         // Configure USB turnaround time for full speed (5 AHB clocks)
         usb_otg_global.gusbcfg.modify(|_, w| w.trdt().bits(0x9));
 
-        rprintln!("Step 3c: DISABLING VBUS sensing - KEY for USB-C standalone!");
-        // CRITICAL: Disable VBUS sensing for USB-C only operation!
-        // This is THE most important setting for USB-C standalone operation
-        // Without this, the device will never enumerate when only USB-C is connected
+        // CRITICAL: Disable VBUS sensing for USB-C only operation! Without
+        // this the device never enumerates when only USB-C is connected.
         usb_otg_global.gccfg.modify(|_, w| {
-            w.vbden()
-                .clear_bit() // DISABLE VBUS detection - critical for USB-C only
-                .pwrdwn()
-                .clear_bit() // Power up the USB transceiver
+            w.vbden().clear_bit().pwrdwn().clear_bit()
         });
-
-        delay_ms(20);
-        rprintln!("âœ… USB hardware configured for USB-C standalone - VBUS sensing DISABLED");
     }
+    rprintln!("USB hardware configured for USB-C standalone - VBUS sensing DISABLED");
 
-    rprintln!("Step 4: Creating USB peripheral (USB2 OTG FS)");
-    // USB endpoint memory - increased size for better buffering
+    // USB endpoint memory
     static mut EP_MEMORY: [u32; 2048] = [0; 2048];
 
-    // Use USB2 (OTG_FS on PA11/PA12) - this is connected to CN13 on the board
     let usb = USB2::new(
         dp.OTG2_HS_GLOBAL,
         dp.OTG2_HS_DEVICE,
@@ -243,326 +294,284 @@ fn main() -> ! {
         &ccdr.clocks,
     );
 
-    rprintln!("Step 5: Creating USB bus");
+    // `UsbBusAllocator` must outlive every `UsbDevice`/`SerialPort` built from
+    // it, and both of those are shared with the interrupt handler, so the
+    // allocator itself has to live in `'static` storage.
+    static mut USB_BUS: MaybeUninit<UsbBusAllocator<Usb2Bus>> = MaybeUninit::uninit();
     #[allow(static_mut_refs)]
-    let usb_bus = UsbBus::new(usb, unsafe { &mut EP_MEMORY });
-
-    rprintln!("Step 6: Creating serial port");
-    let mut serial = SerialPort::new(&usb_bus);
+    let usb_bus: &'static UsbBusAllocator<Usb2Bus> = unsafe {
+        USB_BUS.write(UsbBus::new(usb, &mut *core::ptr::addr_of_mut!(EP_MEMORY)));
+        USB_BUS.assume_init_ref()
+    };
 
-    rprintln!("Step 7: Building USB device");
-    rprintln!("Step 7a: Creating device builder");
-    // Use STM32 VID/PID for CDC device (0x0483:0x5740)
-    let mut usb_dev = UsbDeviceBuilder::new(&usb_bus, UsbVidPid(0x0483, 0x5740))
+    let serial = SerialPort::new(usb_bus);
+    let usb_dev = UsbDeviceBuilder::new(usb_bus, UsbVidPid(0x0483, 0x5740))
         .device_class(USB_CLASS_CDC)
         .build();
 
-    rprintln!("Step 8: USB device built successfully!");
-
-    rprintln!("âœ… USB device initialized for USB-C standalone operation!");
+    rprintln!("USB device initialized for USB-C standalone operation!");
 
-    // Optimized USB enumeration for USB-C standalone
-    rprintln!("ðŸ”„ Starting USB enumeration for USB-C standalone...");
+    cortex_m::interrupt::free(|cs| {
+        SHARED_USB.borrow(cs).replace(Some(SharedUsb {
+            usb_dev,
+            serial,
+            handler: UsbMessageHandler::new(),
+            signing_state: SigningState::WaitingForMessage,
+            usb_state: UsbConnectionState::Disconnected,
+        }));
+    });
 
-    // Initial delay to allow USB hardware to stabilize
-    delay_ms(500);
-
-    // Aggressive USB polling to ensure reliable enumeration with USB-C only
-    rprintln!("ðŸ”„ Performing intensive USB polling for reliable USB-C enumeration...");
-    for i in 0..200 {
-        // Increased cycles for better USB-C compatibility
-        usb_dev.poll(&mut [&mut serial]);
-
-        // Check if we've enumerated successfully
-        if usb_dev.state() == UsbDeviceState::Configured {
-            rprintln!("âœ… USB enumeration successful after {} cycles!", i + 1);
-            break;
-        }
-
-        // Very fast polling for USB-C compatibility
-        delay_ms(2);
-
-        if i % 50 == 0 {
-            rprintln!(
-                "USB enumeration cycle {}/200 (state: {:?})",
-                i + 1,
-                usb_dev.state()
-            );
-        }
+    // Enable the USB interrupt now that SHARED_USB is populated; from here on
+    // USB polling happens entirely in `OTG_HS`, not in this loop.
+    unsafe {
+        cortex_m::peripheral::NVIC::unmask(interrupt::OTG_HS);
     }
 
-    // Final state check
-    match usb_dev.state() {
-        UsbDeviceState::Configured => {
-            rprintln!("âœ… USB-C enumeration SUCCESSFUL - device ready!");
-        }
-        state => {
-            rprintln!(
-                "âš ï¸  USB enumeration incomplete (state: {:?}) - will continue polling in main loop",
-                state
-            );
-        }
-    }
+    rprintln!("\n=== USB-C STANDALONE MODE ACTIVE ===");
+    rprintln!("Device powered from USB-C only (no ST-LINK needed)");
+    rprintln!("Send a message via USB to sign with Falcon512");
+    rprintln!("Press K1(PC13) or BOOT0(PB2) button to confirm signing\n");
+
+    let mut led_blink_started_ms = timer::now_ms();
+    let mut status_blink_started_ms = timer::now_ms();
+    let k1_debounce = timer::Debouncer::new();
+    let boot_debounce = timer::Debouncer::new();
+    const DEBOUNCE_MS: u32 = 20;
 
-    rprintln!("\nðŸ”Œ === USB-C STANDALONE MODE ACTIVE ===");
-    rprintln!("âœ… Device powered from USB-C only (no ST-LINK needed)");
-    rprintln!("âœ… USB CDC device ready for communication");
-    rprintln!("âœ… VBUS sensing disabled for USB-C compatibility");
-    rprintln!("ðŸ“± Send a message via USB to sign with Falcon512");
-    rprintln!("ðŸ”˜ Press K1(PC13) or BOOT0(PB2) button to confirm signing\n");
-
-    // Create USB message handler
-    let mut usb_handler = UsbMessageHandler::new();
-    let mut state = SigningState::WaitingForMessage;
-    let mut usb_state = UsbConnectionState::Disconnected;
-    let mut blink_counter = 0u32;
-    let mut led_counter = 0u32;
-    let mut usb_poll_counter = 0u32;
-    let mut last_usb_state = UsbDeviceState::Default;
+    // Reseed the signing RNG from the TRNG every this-many signatures so a
+    // long-running device isn't drawing randomness from one boot-time seed
+    // forever.
+    const RESEED_INTERVAL: u32 = 64;
+    let mut signatures_since_reseed: u32 = 0;
 
     loop {
-        // Poll USB continuously and frequently - CRITICAL for stable enumeration
-        usb_poll_counter += 1;
-        usb_dev.poll(&mut [&mut serial]);
-
-        // Check USB device state and handle state changes
-        let current_usb_state = usb_dev.state();
-        if current_usb_state != last_usb_state {
-            rprintln!(
-                "USB state changed: {:?} -> {:?}",
-                last_usb_state,
-                current_usb_state
-            );
-            last_usb_state = current_usb_state;
-
-            // Update our connection state tracking
-            match current_usb_state {
-                UsbDeviceState::Default => {
-                    if usb_state != UsbConnectionState::Connecting {
-                        rprintln!("USB: Connecting...");
-                        usb_state = UsbConnectionState::Connecting;
-                    }
-                }
-                UsbDeviceState::Configured => {
-                    if usb_state != UsbConnectionState::Connected {
-                        rprintln!("USB: Connected and configured!");
-                        usb_state = UsbConnectionState::Connected;
-                        // Reset message handler on new connection
-                        usb_handler.clear_buffer();
-                    }
-                }
-                UsbDeviceState::Suspend => {
-                    rprintln!("USB: Suspended");
-                    usb_state = UsbConnectionState::Suspended;
-                }
-                _ => {
-                    if usb_state != UsbConnectionState::Disconnected {
-                        rprintln!("USB: Disconnected");
-                        usb_state = UsbConnectionState::Disconnected;
-                        // Clear any pending messages on disconnect
-                        usb_handler.clear_buffer();
-                        state = SigningState::WaitingForMessage;
-                    }
-                }
-            }
-        }
+        // Idle until the next interrupt (OTG_HS USB activity or the 1kHz
+        // SysTick tick) instead of busy-polling.
+        cortex_m::asm::wfi();
 
-        // Handle USB suspend/resume for better power management
-        if usb_state == UsbConnectionState::Suspended {
-            // In suspend state, poll less frequently to save power
-            if usb_poll_counter % 1000 == 0 {
-                // Check if we've resumed
-                continue;
-            }
-        }
+        let now = timer::now_ms();
 
-        // USB-C standalone status indication via LED
-        if usb_poll_counter % 25000 == 0 {
+        // Drive the USB-C connection status LED from the last-observed state.
+        let usb_state = cortex_m::interrupt::free(|cs| *LAST_USB_STATE.borrow(cs).borrow());
+        if usb_state != UsbConnectionState::Connected && timer::elapsed_since(status_blink_started_ms, 200) {
+            status_blink_started_ms = now;
             match usb_state {
-                UsbConnectionState::Disconnected => {
-                    // Slow blink: Device powered from USB-C but not enumerated yet
-                    if (usb_poll_counter / 25000) % 4 < 2 {
-                        led.set_high();
-                    } else {
-                        led.set_low();
-                    }
-                }
-                UsbConnectionState::Connecting => {
-                    // Fast blink: USB-C enumeration in progress
-                    led.toggle();
-                }
-                UsbConnectionState::Suspended => {
-                    // Very slow pulse: USB suspended (host may be sleeping)
-                    if (usb_poll_counter / 25000) % 8 < 1 {
-                        led.set_high();
-                    } else {
-                        led.set_low();
-                    }
-                }
-                UsbConnectionState::Connected => {
-                    // LED behavior handled by signing state machine
-                }
+                UsbConnectionState::Disconnected => led.toggle(),
+                UsbConnectionState::Connecting => led.toggle(),
+                UsbConnectionState::Suspended => led.set_low(),
+                UsbConnectionState::Connected => {}
             }
         }
 
-        // Allow message processing when USB is connected OR connecting (more permissive)
-        // This allows the device to work even during USB enumeration
-        let usb_ready = usb_state == UsbConnectionState::Connected
-            || usb_state == UsbConnectionState::Connecting;
-
-        if !usb_ready {
-            continue;
+        // Run the part of the signing state machine that the ISR can't do:
+        // blinking while waiting for a button, debouncing the buttons, and
+        // performing the actual (slow, allocating) Falcon signature.
+        let should_blink_waiting = cortex_m::interrupt::free(|cs| {
+            matches!(
+                SHARED_USB.borrow(cs).borrow().as_ref().map(|s| &s.signing_state),
+                Some(SigningState::WaitingForMessage)
+            )
+        });
+        if should_blink_waiting && timer::elapsed_since(led_blink_started_ms, 500) {
+            led_blink_started_ms = now;
+            led.toggle();
         }
 
-        match state {
-            SigningState::WaitingForMessage => {
-                // Try to read from USB
-                if let Some(_message) = usb_handler.try_read_message(&mut serial) {
-                    rprintln!("Message received! Waiting for button press to sign...");
-                    state = SigningState::MessageReceived;
-                    blink_counter = 0;
-                }
+        let waiting_for_button = cortex_m::interrupt::free(|cs| {
+            matches!(
+                SHARED_USB.borrow(cs).borrow().as_ref().map(|s| &s.signing_state),
+                Some(SigningState::MessageReceived)
+            )
+        });
 
-                // Optimized blink when waiting and connected (non-blocking)
-                led_counter += 1;
-                if led_counter % 30000 == 0 {
-                    // Faster blink for better responsiveness
-                    led.toggle();
-                }
+        if waiting_for_button {
+            if timer::elapsed_since(led_blink_started_ms, 100) {
+                led_blink_started_ms = now;
+                led.toggle();
             }
 
-            SigningState::MessageReceived => {
-                // Continue USB polling during button wait
-                if usb_poll_counter % 100 == 0 {
-                    // Check if USB disconnected during button wait (more permissive)
-                    if usb_state == UsbConnectionState::Disconnected {
-                        rprintln!("USB disconnected during button wait, resetting...");
-                        usb_handler.clear_buffer();
-                        state = SigningState::WaitingForMessage;
-                        continue;
-                    }
-                }
+            let k1_pressed = k1_debounce.update(button_key.is_high(), DEBOUNCE_MS);
+            let boot_pressed = boot_debounce.update(button_boot.is_high(), DEBOUNCE_MS);
+            let confirmed = k1_pressed || boot_pressed;
 
-                // Flash LED rapidly until button press (optimized)
-                blink_counter += 1;
-                if blink_counter % 5000 == 0 {
-                    // Faster flashing for better user feedback
-                    led.toggle();
-                }
+            if confirmed {
+                rprintln!(
+                    "Button {} pressed! Starting signing...",
+                    if k1_pressed { "K1(PC13)" } else { "BOOT0(PB2)" }
+                );
 
-                // Debug: Print button states periodically
-                if blink_counter % 100000 == 0 {
-                    rprintln!(
-                        "Button states - K1(PC13): {}, BOOT0(PB2): {}",
-                        if button_key.is_high() {
-                            "PRESSED"
-                        } else {
-                            "not pressed"
-                        },
-                        if button_boot.is_high() {
-                            "PRESSED"
-                        } else {
-                            "not pressed"
-                        }
-                    );
+                // Three quick confirmation blinks.
+                for _ in 0..3 {
+                    led.set_high();
+                    cortex_m::asm::delay(2_000_000);
+                    led.set_low();
+                    cortex_m::asm::delay(2_000_000);
                 }
+                led.set_high();
 
-                // Check both buttons (active HIGH - pressed = HIGH)
-                if button_key.is_high() || button_boot.is_high() {
-                    let btn_name = if button_key.is_high() {
-                        "K1(PC13)"
-                    } else {
-                        "BOOT0(PB2)"
-                    };
-                    rprintln!("Button {} pressed! Starting signing...", btn_name);
-
-                    // Confirmation blinks: 3 quick blinks with optimized USB polling
-                    for _ in 0..3 {
-                        led.set_high();
-                        for _ in 0..5000 {
-                            // Reduced for faster blinks
-                            usb_dev.poll(&mut [&mut serial]);
-                            cortex_m::asm::nop();
-                        }
-                        led.set_low();
-                        for _ in 0..5000 {
-                            // Reduced for faster blinks
-                            usb_dev.poll(&mut [&mut serial]);
-                            cortex_m::asm::nop();
-                        }
+                cortex_m::interrupt::free(|cs| {
+                    if let Some(shared) = SHARED_USB.borrow(cs).borrow_mut().as_mut() {
+                        shared.signing_state = SigningState::Signing;
                     }
+                });
+            }
+        }
+
+        let ready_to_sign = cortex_m::interrupt::free(|cs| {
+            matches!(
+                SHARED_USB.borrow(cs).borrow().as_ref().map(|s| &s.signing_state),
+                Some(SigningState::Signing)
+            )
+        });
 
-                    state = SigningState::Signing;
-                    led.set_high(); // LED stays on during signing
+        if ready_to_sign {
+            // Sign outside the critical section: `sign_message` allocates
+            // and can take a while, and holding the USB mutex for that long
+            // would starve the interrupt handler.
+            let message: alloc::vec::Vec<u8> = cortex_m::interrupt::free(|cs| {
+                SHARED_USB
+                    .borrow(cs)
+                    .borrow()
+                    .as_ref()
+                    .map(|s| s.handler.get_message().to_vec())
+                    .unwrap_or_default()
+            });
+
+            // Advance the counter before signing so it can be folded into
+            // the signed payload itself (see `Signer::sign_message`) rather
+            // than just riding along unauthenticated in the response frame.
+            let counter = replay_counter::increment();
+            rprintln!("Signing message of {} bytes (counter {})...", message.len(), counter);
+            let sig_bytes = signer.sign_message(counter, &message);
+
+            signatures_since_reseed += 1;
+            if signatures_since_reseed >= RESEED_INTERVAL {
+                let mut fresh_seed = [0u8; 32];
+                if hw_rng.try_fill_bytes(&mut fresh_seed).is_ok() {
+                    signer.reseed(fresh_seed);
+                    rprintln!("Reseeded signing RNG from hardware TRNG");
+                } else {
+                    rprintln!("WARNING: periodic TRNG reseed failed; continuing with current RNG state");
                 }
+                signatures_since_reseed = 0;
             }
 
-            SigningState::Signing => {
-                // Verify USB is still connected before signing (more permissive)
-                if usb_state == UsbConnectionState::Disconnected {
-                    rprintln!("USB disconnected during signing, aborting...");
-                    usb_handler.clear_buffer();
-                    state = SigningState::WaitingForMessage;
-                    led.set_low();
-                    continue;
+            cortex_m::interrupt::free(|cs| {
+                if let Some(shared) = SHARED_USB.borrow(cs).borrow_mut().as_mut() {
+                    shared.handler.send_signed_response(
+                        &mut shared.serial,
+                        counter,
+                        &message,
+                        &sig_bytes,
+                        &public_key_bytes,
+                    );
+                    shared.handler.clear_buffer();
+                    shared.signing_state = SigningState::WaitingForMessage;
                 }
+            });
 
-                // Get the message from the handler's buffer
-                let message = usb_handler.get_message();
-
-                // Sign the message (LED is already on)
-                rprintln!("Signing message of {} bytes...", message.len());
-                let sig_bytes = signer.sign_message(message);
-
-                // Send response via USB with retry logic
-                let mut send_attempts = 0;
-                let max_attempts = 3;
-                while send_attempts < max_attempts {
-                    // Check USB connection before sending (more permissive)
-                    if usb_state == UsbConnectionState::Disconnected {
-                        rprintln!("USB disconnected before sending response");
-                        break;
-                    }
-
-                    usb_handler.send_signed_response(&mut serial, message, &sig_bytes, &PK_BYTES);
+            led.set_low();
+            rprintln!("Signing complete! Ready for next message\n");
+        }
 
-                    // Give time for data to be sent and poll USB
-                    for _ in 0..1000 {
-                        usb_dev.poll(&mut [&mut serial]);
-                        cortex_m::asm::nop();
-                    }
+        let keypair_requested = cortex_m::interrupt::free(|cs| {
+            matches!(
+                SHARED_USB.borrow(cs).borrow().as_ref().map(|s| &s.signing_state),
+                Some(SigningState::KeypairRequested)
+            )
+        });
 
-                    send_attempts += 1;
-                    if send_attempts < max_attempts {
-                        rprintln!("Retrying response send (attempt {})", send_attempts + 1);
-                        delay_ms(10);
-                    }
-                    break; // For now, don't retry - just send once
+        if keypair_requested {
+            rprintln!("Generating new Falcon512 keypair on device request...");
+            let (secret_key, new_public_key_bytes) = generate_and_store_keypair(signer.rng());
+            signer.set_secret_key(secret_key);
+            public_key_bytes = new_public_key_bytes;
+
+            cortex_m::interrupt::free(|cs| {
+                if let Some(shared) = SHARED_USB.borrow(cs).borrow_mut().as_mut() {
+                    shared
+                        .handler
+                        .send_keypair_generated(&mut shared.serial, &public_key_bytes);
+                    shared.handler.clear_buffer();
+                    shared.signing_state = SigningState::WaitingForMessage;
                 }
+            });
+            rprintln!("Keypair generated and persisted\n");
+        }
 
-                // Clear buffer and return to waiting
-                usb_handler.clear_buffer();
-                state = SigningState::WaitingForMessage;
+        let export_requested = cortex_m::interrupt::free(|cs| {
+            matches!(
+                SHARED_USB.borrow(cs).borrow().as_ref().map(|s| &s.signing_state),
+                Some(SigningState::ExportRequested)
+            )
+        });
 
-                // Success: LED off, then 3 optimized blinks with continuous USB polling
-                led.set_low();
-                for _ in 0..3 {
-                    for _ in 0..100000 {
-                        // Reduced for faster completion
-                        usb_dev.poll(&mut [&mut serial]);
-                        cortex_m::asm::nop();
-                    }
-                    led.set_high();
-                    for _ in 0..100000 {
-                        // Reduced for faster completion
-                        usb_dev.poll(&mut [&mut serial]);
-                        cortex_m::asm::nop();
-                    }
-                    led.set_low();
+        if export_requested {
+            cortex_m::interrupt::free(|cs| {
+                if let Some(shared) = SHARED_USB.borrow(cs).borrow_mut().as_mut() {
+                    shared
+                        .handler
+                        .send_public_key(&mut shared.serial, &public_key_bytes);
+                    shared.handler.clear_buffer();
+                    shared.signing_state = SigningState::WaitingForMessage;
                 }
+            });
+            rprintln!("Public key exported\n");
+        }
+    }
+}
 
-                rprintln!("Signing complete! Ready for next message\n");
+#[interrupt]
+fn OTG_HS() {
+    cortex_m::interrupt::free(|cs| {
+        let mut shared_ref = SHARED_USB.borrow(cs).borrow_mut();
+        let Some(shared) = shared_ref.as_mut() else {
+            return;
+        };
+
+        shared.usb_dev.poll(&mut [&mut shared.serial]);
+
+        let current_state = shared.usb_dev.state();
+        let new_connection_state = match current_state {
+            UsbDeviceState::Configured => UsbConnectionState::Connected,
+            UsbDeviceState::Suspend => UsbConnectionState::Suspended,
+            UsbDeviceState::Default => UsbConnectionState::Connecting,
+            _ => UsbConnectionState::Disconnected,
+        };
+
+        if new_connection_state != shared.usb_state {
+            if new_connection_state == UsbConnectionState::Connected {
+                shared.handler.clear_buffer();
+            }
+            if new_connection_state == UsbConnectionState::Disconnected {
+                shared.handler.clear_buffer();
+                shared.signing_state = SigningState::WaitingForMessage;
             }
+            shared.usb_state = new_connection_state;
+            LAST_USB_STATE.borrow(cs).replace(new_connection_state);
         }
-    }
+
+        // `UsbDeviceState` only tracks coarse enumeration/suspend state: a
+        // host can close the serial port (dropping DTR) without the device
+        // ever leaving `Configured`. Treat DTR-deasserted the same as a
+        // disconnect so a closed-then-reopened terminal doesn't see a
+        // half-finished signing flow from the previous session.
+        if current_state == UsbDeviceState::Configured && !shared.serial.dtr() {
+            shared.handler.clear_buffer();
+            shared.signing_state = SigningState::WaitingForMessage;
+        }
+
+        if matches!(shared.signing_state, SigningState::WaitingForMessage)
+            && shared.handler.try_read_message(&mut shared.serial).is_some()
+        {
+            shared.signing_state = match shared.handler.last_frame_type() {
+                FrameType::SignRequest => SigningState::MessageReceived,
+                FrameType::GenerateKeypair => SigningState::KeypairRequested,
+                FrameType::ExportPublicKey => SigningState::ExportRequested,
+                // The handler only ever completes a frame for one of the
+                // three request types above.
+                _ => SigningState::WaitingForMessage,
+            };
+        }
+    });
 }
@@ -0,0 +1,106 @@
+//! Flash-persisted, monotonically increasing signing counter.
+//!
+//! Every successful signature binds to the next value of this counter, and
+//! the counter is echoed back in the response frame, so the host can detect
+//! replayed or dropped responses by checking it only ever increases by
+//! exactly one. Uses the same ring-of-slots wear-leveling scheme as
+//! [`crate::flash_store`], over its own sector so the two don't collide: an
+//! erase that misses this module's own sector and lands on `flash_store`'s
+//! (or vice versa) corrupts whichever record happens to live there.
+
+use crate::crc32::crc32;
+use crate::flash_io::{self, FLASH_WORD};
+
+pub(crate) const STORE_BASE: u32 = 0x080A_0000;
+pub(crate) const STORE_SIZE: usize = 8 * 1024;
+/// Sector 5 of bank 1 on the STM32H743 (128 KB sectors, `sector n` spans
+/// `0x08000000 + n * 0x20000` .. `+ 0x1FFFF`). Two full sectors below
+/// `flash_store`'s sector 7 so the two stores' erases never touch the same
+/// physical flash; [`assert_sectors_disjoint`] checks this at compile time.
+pub(crate) const SECTOR_NUMBER: u8 = 5;
+
+const _: () = assert_sectors_disjoint();
+
+/// Compile-time check that this module's flash range doesn't overlap
+/// [`crate::flash_store`]'s. A previous version of this module used
+/// `0x080E_0000`/sector 6, which was actually *inside* `flash_store`'s
+/// sector 7 — `flash_store::store()`'s sector erase silently wiped the
+/// counter, and this module's own `erase_sector(6)` erased an unrelated
+/// sector, so the counter's real sector was never erased and `load()`
+/// eventually returned stale/corrupt data.
+const fn assert_sectors_disjoint() -> () {
+    assert!(SECTOR_NUMBER != crate::flash_store::SECTOR_NUMBER);
+    let start = STORE_BASE as u64;
+    let end = start + STORE_SIZE as u64;
+    let other_start = crate::flash_store::STORE_BASE as u64;
+    let other_end = other_start + crate::flash_store::STORE_SIZE as u64;
+    assert!(end <= other_start || other_end <= start);
+}
+
+const RECORD_MAGIC: u32 = 0xC0_117ED;
+const BODY_SIZE: usize = 4 + 8; // magic + counter
+const RECORD_SIZE: usize = BODY_SIZE + 4; // + crc32
+const SLOT_SIZE: usize = (RECORD_SIZE + FLASH_WORD - 1) / FLASH_WORD * FLASH_WORD;
+const NUM_SLOTS: usize = STORE_SIZE / SLOT_SIZE;
+
+fn slot_bytes(slot: usize) -> &'static [u8] {
+    unsafe {
+        core::slice::from_raw_parts((STORE_BASE as usize + slot * SLOT_SIZE) as *const u8, SLOT_SIZE)
+    }
+}
+
+fn parse_slot(slot: usize) -> Option<u64> {
+    let bytes = slot_bytes(slot);
+    if u32::from_le_bytes(bytes[0..4].try_into().unwrap()) != RECORD_MAGIC {
+        return None;
+    }
+    let expected_crc = u32::from_le_bytes(bytes[BODY_SIZE..BODY_SIZE + 4].try_into().unwrap());
+    if crc32(&bytes[0..BODY_SIZE]) != expected_crc {
+        return None;
+    }
+    Some(u64::from_le_bytes(bytes[4..12].try_into().unwrap()))
+}
+
+fn is_slot_erased(slot: usize) -> bool {
+    slot_bytes(slot).iter().all(|&b| b == 0xFF)
+}
+
+fn newest_slot() -> Option<(usize, u64)> {
+    (0..NUM_SLOTS)
+        .filter_map(|slot| parse_slot(slot).map(|counter| (slot, counter)))
+        .max_by_key(|(_, counter)| *counter)
+}
+
+/// Current signing counter, or 0 if nothing has been signed yet.
+pub fn load() -> u64 {
+    newest_slot().map(|(_, counter)| counter).unwrap_or(0)
+}
+
+/// Persist `counter + 1` as the newest value and return it. Call once per
+/// successful signature.
+pub fn increment() -> u64 {
+    let (slot, current) = match newest_slot() {
+        Some((slot, counter)) if slot + 1 < NUM_SLOTS && is_slot_erased(slot + 1) => {
+            (slot + 1, counter)
+        }
+        Some((_, counter)) => {
+            flash_io::erase_sector(SECTOR_NUMBER);
+            (0, counter)
+        }
+        None => {
+            if !is_slot_erased(0) {
+                flash_io::erase_sector(SECTOR_NUMBER);
+            }
+            (0, 0)
+        }
+    };
+    let next = current + 1;
+
+    let mut buf = [0xFFu8; SLOT_SIZE];
+    buf[0..4].copy_from_slice(&RECORD_MAGIC.to_le_bytes());
+    buf[4..12].copy_from_slice(&next.to_le_bytes());
+    buf[BODY_SIZE..BODY_SIZE + 4].copy_from_slice(&crc32(&buf[0..BODY_SIZE]).to_le_bytes());
+
+    flash_io::program(STORE_BASE + (slot * SLOT_SIZE) as u32, &buf);
+    next
+}
@@ -4,39 +4,134 @@ extern crate alloc;
 
 use alloc::vec::Vec;
 use falcon_rust::falcon512;
+use falcon_rust::workspace::FalconWorkspace512;
 use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
 use rtt_target::rprintln;
+use sha3::{digest::Update, Shake256};
 
 /// Maximum message size (adjust as needed)
 pub const MAX_MESSAGE_SIZE: usize = 512;
 
+/// Context tag for [`Signer::finalize_sign`]'s pre-hashed signatures, so a
+/// streamed signature can never be confused with a raw-message or
+/// over-USB-prehashed one signed under a different context.
+const STREAM_CONTEXT: &[u8] = b"stm32-firmware/streamed-sign/v1";
+
 /// Signer handles Falcon512 signing operations
 pub struct Signer {
     secret_key: falcon512::SecretKey,
     rng: ChaCha20Rng,
+    /// Passed to `sign_with_rng_in` on every `sign_message` call.
+    /// `sign_with_rng_in` does not use it, so each signature still allocates
+    /// its own FFT/polynomial scratch space on the heap.
+    workspace: FalconWorkspace512,
+    /// Running SHAKE256 absorb state for [`Signer::update`]/[`Signer::finalize_sign`],
+    /// so a message larger than `MAX_MESSAGE_SIZE` can be hashed 64 bytes at a
+    /// time as it streams in over USB instead of being buffered whole.
+    hasher: Shake256,
 }
 
 impl Signer {
     /// Create a new Signer with the given secret key and RNG
     pub fn new(secret_key: falcon512::SecretKey, rng: ChaCha20Rng) -> Self {
-        Self { secret_key, rng }
+        Self {
+            secret_key,
+            rng,
+            workspace: FalconWorkspace512::new(),
+            hasher: Shake256::default(),
+        }
+    }
+
+    /// Swap in a freshly generated (or freshly loaded from flash) secret key.
+    pub fn set_secret_key(&mut self, secret_key: falcon512::SecretKey) {
+        self.secret_key = secret_key;
     }
 
-    /// Sign a message and return the signature bytes as a Vec
-    pub fn sign_message(&mut self, message: &[u8]) -> Vec<u8> {
-        rprintln!("Signing {} byte message...", message.len());
-        let signature = falcon512::sign_with_rng(message, &self.secret_key, &mut self.rng);
+    /// Borrow the signer's RNG, e.g. to seed an on-device `falcon512::keygen`.
+    pub fn rng(&mut self) -> &mut ChaCha20Rng {
+        &mut self.rng
+    }
+
+    /// Re-seed the signing RNG from fresh entropy, e.g. periodically drawn
+    /// from the hardware TRNG so a long-running device isn't signing
+    /// indefinitely off a single boot-time seed.
+    pub fn reseed(&mut self, seed: [u8; 32]) {
+        self.rng = ChaCha20Rng::from_seed(seed);
+    }
+
+    /// Sign `counter || message` and return the signature bytes as a Vec.
+    ///
+    /// Binding the replay counter into what's actually signed (rather than
+    /// just attaching it to the response frame) is the whole point of
+    /// `replay_counter`: a signature over `message` alone would let anyone
+    /// who can see/modify USB traffic swap in a different counter value
+    /// without invalidating the signature, defeating the anti-replay
+    /// guarantee. Callers must pass the same `counter` used in the response
+    /// frame, from the same call to [`crate::replay_counter::increment`].
+    #[allow(deprecated)] // sign_with_rng_in is declined/blocked (see falcon512 docs) but still the widest-available signature
+    pub fn sign_message(&mut self, counter: u64, message: &[u8]) -> Vec<u8> {
+        rprintln!("Signing {} byte message (counter {})...", message.len(), counter);
+        let mut signed_message = Vec::with_capacity(8 + message.len());
+        signed_message.extend_from_slice(&counter.to_le_bytes());
+        signed_message.extend_from_slice(message);
+
+        let signature = falcon512::sign_with_rng_in(
+            &signed_message,
+            &self.secret_key,
+            &mut self.rng,
+            &mut self.workspace,
+        );
         rprintln!("Signature generated!");
 
         signature.to_bytes()
     }
-}
 
-/// Helper function to format byte as hex
-pub fn format_hex(byte: u8) -> [u8; 2] {
-    const HEX_CHARS: &[u8] = b"0123456789ABCDEF";
-    [
-        HEX_CHARS[(byte >> 4) as usize],
-        HEX_CHARS[(byte & 0x0F) as usize],
-    ]
+    /// Absorb the next chunk of a message that's too large to buffer whole.
+    /// Call repeatedly as bytes stream in over USB, then call
+    /// [`Signer::finalize_sign`] once the message is complete.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+    }
+
+    /// Squeeze the digest of everything absorbed via [`Signer::update`] since
+    /// the last call, sign it with [`falcon_rust::falcon512::sign_prehashed`],
+    /// and reset the hasher so the `Signer` is ready for the next message.
+    pub fn finalize_sign(&mut self) -> Vec<u8> {
+        use sha3::digest::{ExtendableOutput, XofReader};
+
+        let mut digest = [0u8; 32];
+        core::mem::replace(&mut self.hasher, Shake256::default())
+            .finalize_xof()
+            .read(&mut digest);
+
+        rprintln!("Signing streamed message (digest {:02x?})...", digest);
+        let signature = falcon512::sign_prehashed(&digest, &self.secret_key, STREAM_CONTEXT, &mut self.rng)
+            .expect("STREAM_CONTEXT is a fixed constant well under u8::MAX bytes");
+        rprintln!("Signature generated!");
+
+        signature.to_bytes()
+    }
+
+    /// Serialize the current secret key to the fixed-size byte layout
+    /// [`crate::flash_store`] writes to flash.
+    pub fn export(&self) -> Vec<u8> {
+        self.secret_key.to_bytes()
+    }
+
+    /// Load a secret key previously produced by [`Signer::export`], replacing
+    /// the current one. Returns `false` and leaves the signer unchanged if
+    /// `bytes` isn't a valid Falcon512 secret key encoding.
+    pub fn import(&mut self, bytes: &[u8]) -> bool {
+        if bytes.len() != crate::flash_store::SK_SIZE {
+            return false;
+        }
+        match falcon512::SecretKey::from_bytes(bytes) {
+            Ok(secret_key) => {
+                self.secret_key = secret_key;
+                true
+            }
+            Err(_) => false,
+        }
+    }
 }
@@ -0,0 +1,76 @@
+//! SysTick-driven millisecond clock.
+//!
+//! The old `delay_ms` was a NOP-spin loop, which burns CPU and makes LED
+//! timing and button debounce drift with clock/voltage. This configures
+//! SysTick to tick once per millisecond and exposes a monotonic `now_ms()`
+//! so the rest of the firmware can schedule work by comparing timestamps
+//! instead of spinning.
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicU32, Ordering};
+use cortex_m::interrupt::Mutex;
+use cortex_m::peripheral::SYST;
+
+static MILLIS: AtomicU32 = AtomicU32::new(0);
+
+/// Debounce state for a single button, tracked by timestamp rather than by
+/// spin-counting.
+pub struct Debouncer {
+    last_change_ms: Mutex<Cell<u32>>,
+    stable_state: Mutex<Cell<bool>>,
+}
+
+impl Debouncer {
+    pub const fn new() -> Self {
+        Self {
+            last_change_ms: Mutex::new(Cell::new(0)),
+            stable_state: Mutex::new(Cell::new(false)),
+        }
+    }
+
+    /// Feed the raw (un-debounced) pin reading. Returns the debounced state
+    /// once it has been stable for at least `debounce_ms`.
+    pub fn update(&self, raw_state: bool, debounce_ms: u32) -> bool {
+        cortex_m::interrupt::free(|cs| {
+            let stable = self.stable_state.borrow(cs);
+            let last_change = self.last_change_ms.borrow(cs);
+            let now = now_ms();
+
+            if raw_state != stable.get() {
+                if now.wrapping_sub(last_change.get()) >= debounce_ms {
+                    stable.set(raw_state);
+                    last_change.set(now);
+                }
+            } else {
+                last_change.set(now);
+            }
+
+            stable.get()
+        })
+    }
+}
+
+/// Initialize SysTick to fire once per millisecond at the given core clock.
+pub fn init(mut syst: SYST, sysclk_hz: u32) {
+    syst.set_clock_source(cortex_m::peripheral::syst::SystClkSource::Core);
+    syst.set_reload((sysclk_hz / 1000) - 1);
+    syst.clear_current();
+    syst.enable_counter();
+    syst.enable_interrupt();
+}
+
+/// Current time in milliseconds since [`init`] was called.
+pub fn now_ms() -> u32 {
+    MILLIS.load(Ordering::Relaxed)
+}
+
+/// Non-blocking replacement for the old busy-wait `delay_ms`: returns `true`
+/// once `duration_ms` have elapsed since `since`.
+pub fn elapsed_since(since: u32, duration_ms: u32) -> bool {
+    now_ms().wrapping_sub(since) >= duration_ms
+}
+
+#[cortex_m_rt::exception]
+fn SysTick() {
+    MILLIS.fetch_add(1, Ordering::Relaxed);
+}
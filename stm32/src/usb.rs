@@ -1,26 +1,89 @@
 //! USB module - handles USB communication and message protocol
+//!
+//! Frames look like:
+//! `[magic:2][type:1][len:u32 LE][payload:len][crc32:u32 LE]`
+//! CDC full-speed packets are capped at 64 bytes, far smaller than a
+//! Falcon512 public key (~897 B) or signature (~650+ B), so both directions
+//! accumulate/emit frames across many `poll()`-driven reads/writes rather
+//! than assuming one packet is one message.
+//!
+//! This replaces the old newline-terminated text protocol (`\n`/`\r` framing
+//! with a `SIGNED:`/hex payload), which corrupted any binary payload
+//! containing those bytes. `parse_frame` already resyncs on a bad magic/type
+//! byte and rejects any `len` past `MAX_MESSAGE_SIZE` before it's trusted.
+//!
+//! Triage note (`chunk2-1` vs `chunk1-2`): a second backlog item asked for
+//! the same framed-binary-protocol-over-newline-text replacement, but spelled
+//! out a different wire format (1-byte type, 2-byte big-endian length, CRC16
+//! trailer) than the one implemented here (4-byte little-endian length,
+//! CRC32 trailer). Those aren't reconcilable as "implement both" — a single
+//! device can only speak one frame format on the wire, and the host-side
+//! `usb-client` binary is already built against this one. Resolution: this
+//! module's framing (already shipped, exercised by `usb-client`, and the
+//! stronger of the two checksums) is the implementation; `chunk2-1` is
+//! declined as a duplicate rather than given a second, incompatible parser.
 
+use alloc::vec::Vec;
 use heapless::Vec as HVec;
 use rtt_target::rprintln;
 use usbd_serial::SerialPort;
 
-use crate::signing::{format_hex, MAX_MESSAGE_SIZE};
+use crate::crc32::crc32;
+use crate::signing::MAX_MESSAGE_SIZE;
+
+/// `[magic:2][type:1][len:u32][crc32:u32]` framing overhead around the payload.
+const FRAME_OVERHEAD: usize = 2 + 1 + 4 + 4;
+const HEADER_LEN: usize = 2 + 1 + 4;
+const MAGIC: [u8; 2] = [0xFA, 0x51];
+
+/// Frame type tags.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FrameType {
+    SignRequest = 0x01,
+    SignedResponse = 0x02,
+    GenerateKeypair = 0x03,
+    KeypairGenerated = 0x04,
+    ExportPublicKey = 0x05,
+    PublicKeyExport = 0x06,
+}
+
+impl FrameType {
+    /// The frame types the host is allowed to send us; anything else gets
+    /// treated as noise and resynced past.
+    fn from_request_byte(byte: u8) -> Option<Self> {
+        match byte {
+            x if x == Self::SignRequest as u8 => Some(Self::SignRequest),
+            x if x == Self::GenerateKeypair as u8 => Some(Self::GenerateKeypair),
+            x if x == Self::ExportPublicKey as u8 => Some(Self::ExportPublicKey),
+            _ => None,
+        }
+    }
+}
 
 /// USB message handler for receiving and sending messages
 pub struct UsbMessageHandler {
+    /// Raw bytes accumulated from `poll()` reads, not yet parsed into a
+    /// complete, CRC-valid frame.
+    raw_buffer: HVec<u8, { MAX_MESSAGE_SIZE + FRAME_OVERHEAD }>,
+    /// Payload of the most recently completed frame.
     message_buffer: HVec<u8, MAX_MESSAGE_SIZE>,
+    /// Type of the frame currently held in `message_buffer`.
+    last_type: FrameType,
 }
 
 impl UsbMessageHandler {
     /// Create a new USB message handler
     pub fn new() -> Self {
         Self {
+            raw_buffer: HVec::new(),
             message_buffer: HVec::new(),
+            last_type: FrameType::SignRequest,
         }
     }
 
-    /// Try to read a message from USB serial port with improved error handling
-    /// Returns Some(message) if a complete message was received, None otherwise
+    /// Try to read a message from USB serial port.
+    /// Returns Some(payload) once a complete, CRC-valid frame has arrived.
     pub fn try_read_message<'a, B: usb_device::bus::UsbBus>(
         &mut self,
         serial: &mut SerialPort<'a, B>,
@@ -29,67 +92,98 @@ impl UsbMessageHandler {
         match serial.read(&mut buf) {
             Ok(count) if count > 0 => {
                 rprintln!("Received {} bytes via USB", count);
-
-                // Append to message buffer with bounds checking
-                for i in 0..count {
-                    if self.message_buffer.push(buf[i]).is_err() {
-                        rprintln!("ERROR: Message too large! Clearing buffer.");
-                        self.message_buffer.clear();
+                for &byte in &buf[..count] {
+                    if self.raw_buffer.push(byte).is_err() {
+                        rprintln!("ERROR: Frame buffer overflow! Resyncing.");
+                        self.raw_buffer.clear();
                         return None;
                     }
                 }
+                self.parse_frame()
+            }
+            Ok(_) => None,
+            Err(usb_device::UsbError::WouldBlock) => None,
+            Err(e) => {
+                rprintln!("USB read error: {:?}", e);
+                self.raw_buffer.clear();
+                None
+            }
+        }
+    }
 
-                // Check for newline (message complete)
-                if buf[..count].contains(&b'\n') || buf[..count].contains(&b'\r') {
-                    // Remove trailing newline/carriage return
-                    while self.message_buffer.last() == Some(&b'\n')
-                        || self.message_buffer.last() == Some(&b'\r')
-                        || self.message_buffer.last() == Some(&b' ')
-                        || self.message_buffer.last() == Some(&b'\t')
-                    {
-                        self.message_buffer.pop();
-                    }
+    /// Drop `count` bytes from the front of `raw_buffer`, used both to
+    /// consume a completed frame and to resync past a bad magic/CRC.
+    fn drop_front(&mut self, count: usize) {
+        let remaining = self.raw_buffer.len() - count;
+        self.raw_buffer.copy_within(count.., 0);
+        self.raw_buffer.truncate(remaining);
+    }
 
-                    if !self.message_buffer.is_empty() {
-                        rprintln!("Message complete: {} bytes", self.message_buffer.len());
-                        // Log first few bytes for debugging
-                        if self.message_buffer.len() >= 8 {
-                            rprintln!(
-                                "Message starts with: {:02x} {:02x} {:02x} {:02x}...",
-                                self.message_buffer[0],
-                                self.message_buffer[1],
-                                self.message_buffer[2],
-                                self.message_buffer[3]
-                            );
-                        }
-                        return Some(&self.message_buffer);
-                    } else {
-                        rprintln!("Empty message received, ignoring");
-                        self.message_buffer.clear();
-                    }
-                }
+    /// Scan `raw_buffer` for a complete, CRC-valid frame, resyncing one byte
+    /// at a time past anything that doesn't look like one.
+    fn parse_frame(&mut self) -> Option<&[u8]> {
+        loop {
+            if self.raw_buffer.len() < HEADER_LEN {
+                return None;
             }
-            Ok(0) => {
-                // No data available - this is normal
+            if self.raw_buffer[0..2] != MAGIC {
+                self.drop_front(1);
+                continue;
             }
-            Ok(_) => {
-                // This handles any other Ok(count) values that might occur
-                // Should not happen in practice but satisfies the compiler
+            let Some(frame_type) = FrameType::from_request_byte(self.raw_buffer[2]) else {
+                rprintln!("Unexpected frame type {:#x}; resyncing", self.raw_buffer[2]);
+                self.drop_front(2);
+                continue;
+            };
+
+            let payload_len = u32::from_le_bytes([
+                self.raw_buffer[3],
+                self.raw_buffer[4],
+                self.raw_buffer[5],
+                self.raw_buffer[6],
+            ]) as usize;
+
+            if payload_len > MAX_MESSAGE_SIZE {
+                rprintln!("Frame claims {} byte payload, too large; resyncing", payload_len);
+                self.drop_front(2);
+                continue;
             }
-            Err(usb_device::UsbError::WouldBlock) => {
-                // No data available - this is normal
+
+            let frame_len = HEADER_LEN + payload_len + 4;
+            if self.raw_buffer.len() < frame_len {
+                return None;
             }
-            Err(e) => {
-                rprintln!("USB read error: {:?}", e);
-                // Clear buffer on error to prevent corruption
-                self.message_buffer.clear();
+
+            let crc_offset = HEADER_LEN + payload_len;
+            let expected_crc = u32::from_le_bytes([
+                self.raw_buffer[crc_offset],
+                self.raw_buffer[crc_offset + 1],
+                self.raw_buffer[crc_offset + 2],
+                self.raw_buffer[crc_offset + 3],
+            ]);
+            let actual_crc = crc32(&self.raw_buffer[2..crc_offset]);
+
+            if actual_crc != expected_crc {
+                rprintln!("Frame CRC mismatch (got {:#x}, want {:#x}); resyncing", actual_crc, expected_crc);
+                self.drop_front(2);
+                continue;
             }
+
+            self.message_buffer.clear();
+            let _ = self
+                .message_buffer
+                .extend_from_slice(&self.raw_buffer[HEADER_LEN..crc_offset]);
+            self.drop_front(frame_len);
+            self.last_type = frame_type;
+
+            rprintln!("Frame complete: {:?}, {} byte payload", frame_type, payload_len);
+            return Some(&self.message_buffer);
         }
-        None
     }
 
     /// Clear the message buffer
     pub fn clear_buffer(&mut self) {
+        self.raw_buffer.clear();
         self.message_buffer.clear();
     }
 
@@ -98,141 +192,103 @@ impl UsbMessageHandler {
         &self.message_buffer
     }
 
-    /// Send a signed response via USB with improved error handling and chunking
-    pub fn send_signed_response<'a, B: usb_device::bus::UsbBus>(
-        &self,
+    /// Type of the most recently completed frame.
+    pub fn last_frame_type(&self) -> FrameType {
+        self.last_type
+    }
+
+    /// Send a value in chunks no larger than a CDC full-speed packet,
+    /// waiting for endpoint space between chunks.
+    fn send_chunked<'a, B: usb_device::bus::UsbBus>(
         serial: &mut SerialPort<'a, B>,
-        message: &[u8],
-        signature: &[u8],
-        public_key: &[u8],
-    ) {
-        rprintln!("Sending response...");
-        rprintln!(
-            "Message: {} bytes, Signature: {} bytes, PubKey: {} bytes",
-            message.len(),
-            signature.len(),
-            public_key.len()
-        );
-
-        // Helper function to send data in chunks with error handling
-        let send_chunked = |serial: &mut SerialPort<'a, B>, data: &[u8]| -> bool {
-            const CHUNK_SIZE: usize = 32; // Send in smaller chunks for reliability
-            let mut offset = 0;
-
-            while offset < data.len() {
-                let end = core::cmp::min(offset + CHUNK_SIZE, data.len());
-                let chunk = &data[offset..end];
-
-                match serial.write(chunk) {
-                    Ok(written) => {
-                        if written == 0 {
-                            rprintln!("USB write returned 0 bytes, retrying...");
-                            return false;
-                        }
-                        offset += written;
-                    }
-                    Err(usb_device::UsbError::WouldBlock) => {
-                        // Buffer full, wait a bit
-                        for _ in 0..1000 {
-                            cortex_m::asm::nop();
-                        }
-                        continue;
-                    }
-                    Err(e) => {
-                        rprintln!("USB write error: {:?}", e);
-                        return false;
+        data: &[u8],
+    ) -> bool {
+        const CHUNK_SIZE: usize = 64;
+        let mut offset = 0;
+        while offset < data.len() {
+            let end = core::cmp::min(offset + CHUNK_SIZE, data.len());
+            match serial.write(&data[offset..end]) {
+                Ok(written) if written > 0 => offset += written,
+                Ok(_) => continue,
+                Err(usb_device::UsbError::WouldBlock) => {
+                    for _ in 0..1000 {
+                        cortex_m::asm::nop();
                     }
                 }
-
-                // Small delay between chunks
-                for _ in 0..100 {
-                    cortex_m::asm::nop();
+                Err(e) => {
+                    rprintln!("USB write error: {:?}", e);
+                    return false;
                 }
             }
-            true
-        };
-
-        // Send response header
-        let header = b"SIGNED:\n";
-        if !send_chunked(serial, header) {
-            rprintln!("Failed to send header");
-            return;
-        }
-
-        // Send original message
-        if !send_chunked(serial, message) {
-            rprintln!("Failed to send message");
-            return;
-        }
-
-        if !send_chunked(serial, b"\nSIGNATURE:\n") {
-            rprintln!("Failed to send signature header");
-            return;
-        }
-
-        // Send signature (hex encoded for readability) in chunks
-        let mut hex_buffer = [0u8; 64]; // Buffer for hex chunks
-        let mut hex_pos = 0;
-
-        for byte in signature.iter() {
-            let hex = format_hex(*byte);
-            if hex_pos + 2 >= hex_buffer.len() {
-                // Send current buffer
-                if !send_chunked(serial, &hex_buffer[..hex_pos]) {
-                    rprintln!("Failed to send signature chunk");
-                    return;
-                }
-                hex_pos = 0;
-            }
-            hex_buffer[hex_pos] = hex[0];
-            hex_buffer[hex_pos + 1] = hex[1];
-            hex_pos += 2;
         }
+        true
+    }
 
-        // Send remaining hex data
-        if hex_pos > 0 {
-            if !send_chunked(serial, &hex_buffer[..hex_pos]) {
-                rprintln!("Failed to send final signature chunk");
-                return;
-            }
-        }
+    /// Assemble a complete, CRC-tagged frame for `frame_type` around `payload`.
+    fn build_frame(frame_type: FrameType, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(HEADER_LEN + payload.len() + 4);
+        frame.extend_from_slice(&MAGIC);
+        frame.push(frame_type as u8);
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(&crc32(&frame[2..]).to_le_bytes());
+        frame
+    }
 
-        if !send_chunked(serial, b"\nPUBLIC_KEY:\n") {
-            rprintln!("Failed to send public key header");
+    /// Send a single frame of `frame_type` with the given payload.
+    fn send_frame<'a, B: usb_device::bus::UsbBus>(
+        serial: &mut SerialPort<'a, B>,
+        frame_type: FrameType,
+        payload: &[u8],
+    ) {
+        if !Self::send_chunked(serial, &Self::build_frame(frame_type, payload)) {
+            rprintln!("Failed to send {:?} frame", frame_type);
             return;
         }
+        rprintln!("Sent {:?} frame ({} byte payload)", frame_type, payload.len());
+    }
 
-        // Send public key (hex encoded for readability) in chunks
-        hex_pos = 0;
-        for byte in public_key.iter() {
-            let hex = format_hex(*byte);
-            if hex_pos + 2 >= hex_buffer.len() {
-                // Send current buffer
-                if !send_chunked(serial, &hex_buffer[..hex_pos]) {
-                    rprintln!("Failed to send public key chunk");
-                    return;
-                }
-                hex_pos = 0;
-            }
-            hex_buffer[hex_pos] = hex[0];
-            hex_buffer[hex_pos + 1] = hex[1];
-            hex_pos += 2;
-        }
+    /// Send a signed response as a single `SignedResponse` frame whose
+    /// payload is
+    /// `[counter:u64][msg_len:u32][message][sig_len:u32][signature][pk_len:u32][public_key]`.
+    /// The counter is the device's monotonic signing counter at the time of
+    /// this signature, letting the host detect replayed or dropped responses.
+    pub fn send_signed_response<'a, B: usb_device::bus::UsbBus>(
+        &self,
+        serial: &mut SerialPort<'a, B>,
+        counter: u64,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) {
+        let mut payload =
+            Vec::with_capacity(20 + message.len() + signature.len() + public_key.len());
+        payload.extend_from_slice(&counter.to_le_bytes());
+        payload.extend_from_slice(&(message.len() as u32).to_le_bytes());
+        payload.extend_from_slice(message);
+        payload.extend_from_slice(&(signature.len() as u32).to_le_bytes());
+        payload.extend_from_slice(signature);
+        payload.extend_from_slice(&(public_key.len() as u32).to_le_bytes());
+        payload.extend_from_slice(public_key);
 
-        // Send remaining hex data
-        if hex_pos > 0 {
-            if !send_chunked(serial, &hex_buffer[..hex_pos]) {
-                rprintln!("Failed to send final public key chunk");
-                return;
-            }
-        }
+        Self::send_frame(serial, FrameType::SignedResponse, &payload);
+    }
 
-        // Send final newline
-        if !send_chunked(serial, b"\n") {
-            rprintln!("Failed to send final newline");
-            return;
-        }
+    /// Send the public key generated by a `GenerateKeypair` command.
+    pub fn send_keypair_generated<'a, B: usb_device::bus::UsbBus>(
+        &self,
+        serial: &mut SerialPort<'a, B>,
+        public_key: &[u8],
+    ) {
+        Self::send_frame(serial, FrameType::KeypairGenerated, public_key);
+    }
 
-        rprintln!("Response sent successfully via USB");
+    /// Send the current public key in reply to an `ExportPublicKey` command.
+    pub fn send_public_key<'a, B: usb_device::bus::UsbBus>(
+        &self,
+        serial: &mut SerialPort<'a, B>,
+        public_key: &[u8],
+    ) {
+        Self::send_frame(serial, FrameType::PublicKeyExport, public_key);
     }
 }
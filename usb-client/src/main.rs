@@ -1,9 +1,33 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 use falcon_rust::falcon512;
 use serialport::SerialPort;
 use std::io::{Read, Write};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// `[magic:2][type:1][len:u32 LE][payload:len][crc32:u32 LE]` framing,
+/// matching `stm32::usb`. Replaces the old newline-terminated
+/// `SIGNED:`/`PUBLIC_KEY:` hex-text protocol, which can't carry raw binary
+/// signature/key bytes and was dropped once the firmware moved to this
+/// framing.
+const MAGIC: [u8; 2] = [0xFA, 0x51];
+const HEADER_LEN: usize = 2 + 1 + 4;
+
+/// Frame type tags, matching `FrameType` in `stm32::usb`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[repr(u8)]
+enum FrameType {
+    SignRequest = 0x01,
+    SignedResponse = 0x02,
+    #[allow(dead_code)]
+    GenerateKeypair = 0x03,
+    #[allow(dead_code)]
+    KeypairGenerated = 0x04,
+    #[allow(dead_code)]
+    ExportPublicKey = 0x05,
+    #[allow(dead_code)]
+    PublicKeyExport = 0x06,
+}
 
 /// USB client for communicating with STM32 Falcon512 signer
 #[derive(Parser, Debug)]
@@ -54,54 +78,24 @@ fn main() -> Result<()> {
     println!("✅ Connected!");
     println!("📤 Sending message: \"{}\"", args.message);
 
-    // Send message with newline
-    let message_with_newline = format!("{}\n", args.message);
-    port.write_all(message_with_newline.as_bytes())
+    send_frame(&mut *port, FrameType::SignRequest, args.message.as_bytes())
         .context("Failed to write to serial port")?;
-    port.flush().context("Failed to flush serial port")?;
 
     println!("⏳ Waiting for STM32 to receive message...");
     println!("👆 Press button B0 on the STM32 board to sign the message");
 
-    // Read response
-    let response = read_response(&mut port, args.timeout)?;
-
-    println!("\n📥 Received response from STM32:");
-    println!("{}", response);
-
-    // Parse signature and public key
-    let sig_start = response
-        .find("SIGNATURE:")
-        .context("Missing SIGNATURE in response - response may be incomplete")?;
-    let pk_start = response
-        .find("PUBLIC_KEY:")
-        .context("Missing PUBLIC_KEY in response - response may be incomplete")?;
-
-    let signature_hex = response[sig_start + 10..pk_start].trim();
-    let public_key_hex = response[pk_start + 11..].trim();
-
-    println!("\n🔐 Signature (hex):");
-    println!("{}", signature_hex);
-
-    println!("\n🔑 Public Key (hex):");
-    println!("{}", public_key_hex);
-
-    // Decode hex signature
-    let sig_bytes = hex_decode(signature_hex).with_context(|| {
-        format!(
-            "Failed to decode signature hex (length: {})",
-            signature_hex.len()
-        )
-    })?;
-    let pk_bytes = hex_decode(public_key_hex).with_context(|| {
-        format!(
-            "Failed to decode public key hex (length: {})",
-            public_key_hex.len()
-        )
-    })?;
+    // Read frames until the SignedResponse arrives
+    let (counter, message, sig_bytes, pk_bytes) =
+        read_signed_response(&mut *port, args.timeout)?;
 
+    println!("\n📥 Received signed response from STM32:");
+    println!("🔢 Counter: {}", counter);
     println!(
-        "\n📊 Decoded {} signature bytes and {} public key bytes",
+        "💬 Message: {}",
+        String::from_utf8_lossy(&message)
+    );
+    println!(
+        "📊 Decoded {} signature bytes and {} public key bytes",
         sig_bytes.len(),
         pk_bytes.len()
     );
@@ -109,22 +103,25 @@ fn main() -> Result<()> {
     // Parse signature and public key
     let signature = falcon512::Signature::from_bytes(&sig_bytes).map_err(|_| {
         anyhow::anyhow!(
-            "Failed to parse signature - expected {} bytes, got {}",
-            666,
+            "Failed to parse signature ({} bytes)",
             sig_bytes.len()
         )
     })?;
     let public_key = falcon512::PublicKey::from_bytes(&pk_bytes).map_err(|_| {
         anyhow::anyhow!(
-            "Failed to parse public key - expected {} bytes, got {}",
-            897,
+            "Failed to parse public key ({} bytes)",
             pk_bytes.len()
         )
     })?;
 
-    // Verify signature
+    // Verify signature. The firmware signs `counter || message`, not
+    // `message` alone (see `Signer::sign_message`), so the counter has to be
+    // folded back in here the same way or every verification fails.
     println!("\n🔍 Verifying signature...");
-    let is_valid = falcon512::verify(args.message.as_bytes(), &signature, &public_key);
+    let mut signed_message = Vec::with_capacity(8 + message.len());
+    signed_message.extend_from_slice(&counter.to_le_bytes());
+    signed_message.extend_from_slice(&message);
+    let is_valid = falcon512::verify(&signed_message, &signature, &public_key);
 
     if is_valid {
         println!("✅ Signature verification PASSED!");
@@ -211,60 +208,132 @@ fn find_stm32_port() -> Result<String> {
     );
 }
 
-fn read_response(port: &mut Box<dyn SerialPort>, timeout_secs: u64) -> Result<String> {
-    let mut response = String::new();
-    let mut buffer = [0u8; 1024];
-    let start = std::time::Instant::now();
+/// CRC32/IEEE (polynomial 0xEDB88320), matching `stm32/src/crc32.rs`. Kept
+/// in sync by hand since this is a separate host-side binary from the
+/// `no_std` firmware crate.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = 0u32.wrapping_sub(crc & 1);
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Assemble a complete, CRC-tagged frame for `frame_type` around `payload`.
+fn build_frame(frame_type: FrameType, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len() + 4);
+    frame.extend_from_slice(&MAGIC);
+    frame.push(frame_type as u8);
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload);
+    frame.extend_from_slice(&crc32(&frame[2..]).to_le_bytes());
+    frame
+}
+
+fn send_frame(port: &mut dyn SerialPort, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+    let frame = build_frame(frame_type, payload);
+    port.write_all(&frame).context("Failed to write frame")?;
+    port.flush().context("Failed to flush serial port")
+}
+
+/// Read bytes from `port` until a complete, CRC-valid `SignedResponse` frame
+/// arrives, resyncing past anything that doesn't look like a valid frame
+/// header (mirrors `UsbMessageHandler::parse_frame` on the firmware side).
+/// Returns `(counter, message, signature, public_key)`.
+fn read_signed_response(
+    port: &mut dyn SerialPort,
+    timeout_secs: u64,
+) -> Result<(u64, Vec<u8>, Vec<u8>, Vec<u8>)> {
     let timeout = Duration::from_secs(timeout_secs);
+    let start = Instant::now();
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 256];
 
     loop {
-        // Check timeout
         if start.elapsed() > timeout {
-            anyhow::bail!("Timeout waiting for response from STM32");
+            bail!("Timeout waiting for response from STM32");
         }
 
-        // Try to read
-        match port.read(&mut buffer) {
-            Ok(n) if n > 0 => {
-                let chunk = String::from_utf8_lossy(&buffer[..n]);
-                response.push_str(&chunk);
+        match port.read(&mut chunk) {
+            Ok(n) if n > 0 => buf.extend_from_slice(&chunk[..n]),
+            Ok(_) => {}
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(e).context("Error reading from serial port"),
+        }
 
-                // Check if we have a complete response (ends with newline after public key)
-                if response.contains("PUBLIC_KEY:") && response.ends_with('\n') {
-                    break;
-                }
+        loop {
+            if buf.len() < HEADER_LEN {
+                break;
             }
-            Ok(_) => {
-                // No data available, sleep briefly
-                std::thread::sleep(Duration::from_millis(100));
+            if buf[0..2] != MAGIC {
+                buf.remove(0);
+                continue;
             }
-            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {
-                // Timeout on read, continue waiting
-                std::thread::sleep(Duration::from_millis(100));
+            let frame_type = buf[2];
+            let payload_len = u32::from_le_bytes([buf[3], buf[4], buf[5], buf[6]]) as usize;
+            let frame_len = HEADER_LEN + payload_len + 4;
+            if frame_len > buf.len() {
+                // Not enough buffered yet; wait for more bytes, unless the
+                // claimed length is absurd, in which case resync.
+                if payload_len > 64 * 1024 {
+                    buf.drain(0..2);
+                    continue;
+                }
+                break;
             }
-            Err(e) => {
-                return Err(e).context("Error reading from serial port");
+
+            let crc_offset = HEADER_LEN + payload_len;
+            let expected_crc =
+                u32::from_le_bytes(buf[crc_offset..crc_offset + 4].try_into().unwrap());
+            let actual_crc = crc32(&buf[2..crc_offset]);
+            if actual_crc != expected_crc {
+                buf.drain(0..2);
+                continue;
             }
+
+            let payload = buf[HEADER_LEN..crc_offset].to_vec();
+            buf.drain(0..frame_len);
+
+            if frame_type != FrameType::SignedResponse as u8 {
+                // Not the frame we're waiting for (e.g. a stray response to
+                // an earlier command); keep reading.
+                continue;
+            }
+
+            return parse_signed_response(&payload);
         }
-    }
 
-    Ok(response)
+        std::thread::sleep(Duration::from_millis(20));
+    }
 }
 
-fn hex_decode(hex_str: &str) -> Result<Vec<u8>> {
-    let hex_str = hex_str.replace('\n', "").replace('\r', "").replace(' ', "");
+/// Parse a `SignedResponse` payload:
+/// `[counter:u64 LE][msg_len:u32 LE][message][sig_len:u32 LE][signature][pk_len:u32 LE][public_key]`.
+fn parse_signed_response(payload: &[u8]) -> Result<(u64, Vec<u8>, Vec<u8>, Vec<u8>)> {
+    let mut offset = 0;
+    let take = |offset: &mut usize, len: usize| -> Result<&[u8]> {
+        if *offset + len > payload.len() {
+            bail!("Truncated SignedResponse payload");
+        }
+        let slice = &payload[*offset..*offset + len];
+        *offset += len;
+        Ok(slice)
+    };
+
+    let counter = u64::from_le_bytes(take(&mut offset, 8)?.try_into().unwrap());
 
-    if hex_str.len() % 2 != 0 {
-        anyhow::bail!("Hex string has odd length");
-    }
+    let msg_len = u32::from_le_bytes(take(&mut offset, 4)?.try_into().unwrap()) as usize;
+    let message = take(&mut offset, msg_len)?.to_vec();
 
-    let mut bytes = Vec::with_capacity(hex_str.len() / 2);
-    for i in (0..hex_str.len()).step_by(2) {
-        let byte_str = &hex_str[i..i + 2];
-        let byte = u8::from_str_radix(byte_str, 16)
-            .with_context(|| format!("Invalid hex byte: {}", byte_str))?;
-        bytes.push(byte);
-    }
+    let sig_len = u32::from_le_bytes(take(&mut offset, 4)?.try_into().unwrap()) as usize;
+    let signature = take(&mut offset, sig_len)?.to_vec();
+
+    let pk_len = u32::from_le_bytes(take(&mut offset, 4)?.try_into().unwrap()) as usize;
+    let public_key = take(&mut offset, pk_len)?.to_vec();
 
-    Ok(bytes)
+    Ok((counter, message, signature, public_key))
 }